@@ -1,24 +1,29 @@
-use crate::{crossdev, InodeFilter, Throttle, WalkOptions, WalkResult};
+use crate::{crossdev, InodeFilter, SortMode, Sortable, Throttle, WalkOptions, WalkResult};
 use anyhow::Result;
 use filesize::PathExt;
 use owo_colors::{AnsiColors as Color, OwoColorize};
-use std::time::Duration;
-use std::{io, path::Path};
+use std::time::{Duration, SystemTime};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 /// Aggregate the given `paths` and write information about them to `out` in a human-readable format.
 /// If `compute_total` is set, it will write an additional line with the total size across all given `paths`.
-/// If `sort_by_size_in_bytes` is set, we will sort all sizes (ascending) before outputting them.
+/// If `sort` is set, all paths are sorted accordingly before outputting them, otherwise they are output as they are given.
+/// `style` controls whether escape sequences are used to decorate the output.
 pub fn aggregate(
     mut out: impl io::Write,
     mut err: Option<impl io::Write>,
     walk_options: WalkOptions,
+    style: OutputStyle,
     compute_total: bool,
-    sort_by_size_in_bytes: bool,
+    sort: Option<SortMode>,
     paths: impl IntoIterator<Item = impl AsRef<Path>>,
 ) -> Result<(WalkResult, Statistics)> {
     let mut res = WalkResult::default();
     let mut stats = Statistics {
-        smallest_file_in_bytes: u128::max_value(),
+        smallest_file_in_bytes: u128::MAX,
         ..Default::default()
     };
     let mut total = 0;
@@ -31,17 +36,25 @@ pub fn aggregate(
         num_roots += 1;
         let mut num_bytes = 0u128;
         let mut num_errors = 0u64;
+        let mut num_entries = 0u64;
         let device_id = match crossdev::init(path.as_ref()) {
             Ok(id) => id,
             Err(_) => {
                 num_errors += 1;
                 res.num_errors += 1;
-                aggregates.push((path.as_ref().to_owned(), num_bytes, num_errors));
+                aggregates.push(PathAggregate {
+                    path: path.as_ref().to_owned(),
+                    num_bytes,
+                    num_errors,
+                    num_entries: 0,
+                    mtime: None,
+                });
                 continue;
             }
         };
         for entry in walk_options.iter_from_path(path.as_ref(), device_id) {
             stats.entries_traversed += 1;
+            num_entries += 1;
             progress.throttled(|| {
                 if let Some(err) = err.as_mut() {
                     write!(err, "Enumerating {} entries\r", stats.entries_traversed).ok();
@@ -50,12 +63,7 @@ pub fn aggregate(
             match entry {
                 Ok(entry) => {
                     let file_size = match entry.client_state {
-                        Some(Ok(ref m))
-                            if !m.is_dir()
-                                && (walk_options.count_hard_links || inodes.add(m))
-                                && (walk_options.cross_filesystems
-                                    || crossdev::is_same_device(device_id, m)) =>
-                        {
+                        Some(Ok(ref m)) if walk_options.is_counted(m, &mut inodes, device_id) => {
                             if walk_options.apparent_size {
                                 m.len()
                             } else {
@@ -70,10 +78,13 @@ pub fn aggregate(
                             num_errors += 1;
                             0
                         }
-                        None => 0, // ignore directory
+                        None => 0, // neither file, symlink nor directory
                     } as u128;
-                    stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(file_size);
-                    stats.smallest_file_in_bytes = stats.smallest_file_in_bytes.min(file_size);
+                    // Directories may account for their own size, but they aren't files.
+                    if !entry.file_type.is_dir() {
+                        stats.largest_file_in_bytes = stats.largest_file_in_bytes.max(file_size);
+                        stats.smallest_file_in_bytes = stats.smallest_file_in_bytes.min(file_size);
+                    }
                     num_bytes += file_size;
                 }
                 Err(_) => num_errors += 1,
@@ -84,16 +95,28 @@ pub fn aggregate(
             write!(err, "\x1b[2K\r").ok();
         }
 
-        if sort_by_size_in_bytes {
-            aggregates.push((path.as_ref().to_owned(), num_bytes, num_errors));
+        if sort.is_some() {
+            aggregates.push(PathAggregate {
+                path: path.as_ref().to_owned(),
+                num_bytes,
+                num_errors,
+                // the path itself isn't one of its entries
+                num_entries: num_entries.saturating_sub(1),
+                mtime: path
+                    .as_ref()
+                    .symlink_metadata()
+                    .and_then(|m| m.modified())
+                    .ok(),
+            });
         } else {
             output_colored_path(
                 &mut out,
                 &walk_options,
+                style,
                 &path,
                 num_bytes,
                 num_errors,
-                path_color_of(&path),
+                Some(&path),
             )?;
         }
         total += num_bytes;
@@ -104,16 +127,17 @@ pub fn aggregate(
         stats.smallest_file_in_bytes = 0;
     }
 
-    if sort_by_size_in_bytes {
-        aggregates.sort_by_key(|&(_, num_bytes, _)| num_bytes);
-        for (path, num_bytes, num_errors) in aggregates.into_iter() {
+    if let Some(sort) = sort {
+        aggregates.sort_by(|l, r| sort.compare(&l.sortable(), &r.sortable()));
+        for aggregate in aggregates.into_iter() {
             output_colored_path(
                 &mut out,
                 &walk_options,
-                &path,
-                num_bytes,
-                num_errors,
-                path_color_of(&path),
+                style,
+                &aggregate.path,
+                aggregate.num_bytes,
+                aggregate.num_errors,
+                Some(&aggregate.path),
             )?;
         }
     }
@@ -122,44 +146,114 @@ pub fn aggregate(
         output_colored_path(
             &mut out,
             &walk_options,
+            style,
             Path::new("total"),
             total,
             res.num_errors,
-            None,
+            None::<&Path>,
         )?;
     }
     Ok((res, stats))
 }
 
+/// The information collected about one of the input paths, to be output once all of them are known.
+struct PathAggregate {
+    path: PathBuf,
+    num_bytes: u128,
+    num_errors: u64,
+    num_entries: u64,
+    mtime: Option<SystemTime>,
+}
+
+impl PathAggregate {
+    fn sortable(&self) -> Sortable<'_> {
+        Sortable {
+            name: &self.path,
+            size: self.num_bytes,
+            entry_count: self.num_entries,
+            mtime: self.mtime,
+        }
+    }
+}
+
 fn path_color_of(path: impl AsRef<Path>) -> Option<Color> {
     (!path.as_ref().is_file()).then_some(Color::Cyan)
 }
 
+/// Write a line with `path` and its size, where `entry` is the path on disk if it is one.
 fn output_colored_path(
     out: &mut impl io::Write,
     options: &WalkOptions,
+    style: OutputStyle,
     path: impl AsRef<Path>,
     num_bytes: u128,
     num_errors: u64,
-    path_color: Option<Color>,
+    entry: Option<impl AsRef<Path>>,
 ) -> std::result::Result<(), io::Error> {
     let size = options.byte_format.display(num_bytes).to_string();
-    let size = size.green();
     let size_width = options.byte_format.width();
-    let path = path.as_ref().display();
-
-    let errors = (num_errors != 0)
-        .then(|| {
-            let plural_s = if num_errors > 1 { "s" } else { "" };
-            format!("  <{num_errors} IO Error{plural_s}>")
-        })
-        .unwrap_or_default();
+    let path = path.as_ref().display().to_string();
+    let (size, path) = if style.colored {
+        let size = format!("{:>size_width$}", size.green());
+        let path = match entry.as_ref().and_then(path_color_of) {
+            Some(color) => path.color(color).to_string(),
+            None => path,
+        };
+        (size, path)
+    } else {
+        (format!("{size:>size_width$}"), path)
+    };
+    let path = match entry
+        .filter(|_| style.hyperlinks)
+        .and_then(|p| file_url(p.as_ref()))
+    {
+        Some(url) => format!("\x1b]8;;{url}\x1b\\{path}\x1b]8;;\x1b\\"),
+        None => path,
+    };
 
-    if let Some(color) = path_color {
-        writeln!(out, "{size:>size_width$} {}{errors}", path.color(color))
+    let errors = if num_errors != 0 {
+        let plural_s = if num_errors > 1 { "s" } else { "" };
+        format!("  <{num_errors} IO Error{plural_s}>")
     } else {
-        writeln!(out, "{size:>size_width$} {path}{errors}")
+        String::new()
+    };
+
+    writeln!(out, "{size} {path}{errors}")
+}
+
+/// Return a `file://` URL for `path`, or `None` if it can't be made absolute.
+fn file_url(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_owned()
+    };
+    #[cfg(not(unix))]
+    let bytes = {
+        let path = path.to_string_lossy();
+        let path = path.trim_start_matches(r"\\?\").replace('\\', "/");
+        format!("/{path}").into_bytes()
+    };
+    let mut url = String::from("file://");
+    for byte in bytes {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
     }
+    Some(url)
+}
+
+/// How the output of [`aggregate()`] is decorated.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct OutputStyle {
+    /// If set, sizes and paths of directories are colored.
+    pub colored: bool,
+    /// If set, paths are emitted as clickable OSC 8 hyperlinks to the files they refer to.
+    pub hyperlinks: bool,
 }
 
 /// Statistics obtained during a filesystem walk
@@ -172,3 +266,21 @@ pub struct Statistics {
     /// The size of the largest file encountered in bytes
     pub largest_file_in_bytes: u128,
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_urls_are_absolute_and_percent_encoded() -> std::io::Result<()> {
+        let dir = std::env::temp_dir().join("dua file url test");
+        std::fs::create_dir_all(&dir)?;
+        let url = file_url(&dir).expect("existing paths can be made absolute");
+        std::fs::remove_dir(&dir)?;
+
+        assert!(url.starts_with("file:///"), "{}", url);
+        assert!(url.ends_with("/dua%20file%20url%20test"), "{}", url);
+        assert_eq!(file_url(&dir), None, "paths that don't exist aren't linked");
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
-use crate::crossdev;
 use crate::traverse::{EntryData, Tree, TreeIndex};
+use crate::{crossdev, InodeFilter};
 use byte_unit::{n_gb_bytes, n_gib_bytes, n_mb_bytes, n_mib_bytes, ByteUnit};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -12,10 +12,6 @@ pub fn get_entry_or_panic(tree: &Tree, node_idx: TreeIndex) -> &EntryData {
         .expect("node should always be retrievable with valid index")
 }
 
-pub(crate) fn get_size_or_panic(tree: &Tree, node_idx: TreeIndex) -> u128 {
-    get_entry_or_panic(tree, node_idx).size
-}
-
 /// Specifies a way to format bytes
 #[derive(Clone, Copy)]
 pub enum ByteFormat {
@@ -166,6 +162,8 @@ pub struct WalkOptions {
     pub threads: usize,
     pub byte_format: ByteFormat,
     pub count_hard_links: bool,
+    /// If set, directories contribute the space they occupy themselves, like `du` does.
+    pub count_directory_sizes: bool,
     pub apparent_size: bool,
     pub sorting: TraversalSorting,
     pub cross_filesystems: bool,
@@ -175,6 +173,20 @@ pub struct WalkOptions {
 type WalkDir = jwalk::WalkDirGeneric<((), Option<Result<std::fs::Metadata, jwalk::Error>>)>;
 
 impl WalkOptions {
+    /// Return `true` if the size of the entry with the given `metadata` should be accounted for.
+    pub(crate) fn is_counted(
+        &self,
+        metadata: &std::fs::Metadata,
+        inodes: &mut InodeFilter,
+        root_device_id: u64,
+    ) -> bool {
+        (if metadata.is_dir() {
+            self.count_directory_sizes
+        } else {
+            self.count_hard_links || inodes.add(metadata)
+        }) && (self.cross_filesystems || crossdev::is_same_device(root_device_id, metadata))
+    }
+
     pub(crate) fn iter_from_path(&self, root: &Path, root_device_id: u64) -> WalkDir {
         WalkDir::new(root)
             .follow_links(false)
@@ -191,9 +203,7 @@ impl WalkOptions {
                         if let Ok(dir_entry) = dir_entry_result {
                             let metadata = dir_entry.metadata();
 
-                            if dir_entry.file_type.is_file() || dir_entry.file_type().is_symlink() {
-                                dir_entry.client_state = Some(metadata);
-                            } else if dir_entry.file_type.is_dir() {
+                            if dir_entry.file_type.is_dir() {
                                 let ok_for_fs = cross_filesystems
                                     || metadata
                                         .as_ref()
@@ -202,6 +212,11 @@ impl WalkOptions {
                                 if !ok_for_fs || ignore_dirs.contains(&dir_entry.path()) {
                                     dir_entry.read_children_path = None;
                                 }
+                                dir_entry.client_state = Some(metadata);
+                            } else if dir_entry.file_type.is_file()
+                                || dir_entry.file_type().is_symlink()
+                            {
+                                dir_entry.client_state = Some(metadata);
                             }
                         }
                     })
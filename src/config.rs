@@ -0,0 +1,380 @@
+use crate::options::{Args, ByteFormat, ByteVisualization, ColorMode, SortMode, Theme};
+use anyhow::{bail, Context, Result};
+use clap::{parser::ValueSource, Arg, ArgAction, ArgMatches, CommandFactory, FromArgMatches};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The environment variable with options to use as if they were passed on the command-line, before all others.
+const OPTIONS_VARIABLE: &str = "DUA_OPTS";
+
+/// The flags which can be set in the configuration file, with the long name of the flag turning each of them off
+/// on the command-line.
+const NEGATED_FLAGS: &[(&str, &str)] = &[
+    ("apparent_size", "no-apparent-size"),
+    ("count_hard_links", "no-count-hard-links"),
+    ("no_directory_sizes", "directory-sizes"),
+    ("stay_on_filesystem", "no-stay-on-filesystem"),
+    ("ls_colors", "no-ls-colors"),
+    ("hyperlinks", "no-hyperlinks"),
+    ("no_confirm", "confirm"),
+];
+
+/// Persistent defaults for command-line options, read from `$XDG_CONFIG_HOME/dua/config.toml`.
+///
+/// Keys are named like the long form of the command-line option they provide a default for.
+#[derive(Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub format: Option<ByteFormat>,
+    pub threads: Option<usize>,
+    pub ignore_dirs: Option<Vec<PathBuf>>,
+    pub apparent_size: Option<bool>,
+    pub count_hard_links: Option<bool>,
+    pub no_directory_sizes: Option<bool>,
+    pub stay_on_filesystem: Option<bool>,
+    pub sort: Option<SortMode>,
+    pub byte_vis: Option<ByteVisualization>,
+    pub theme: Option<Theme>,
+    pub ls_colors: Option<bool>,
+    pub color: Option<ColorMode>,
+    pub hyperlinks: Option<bool>,
+    pub no_confirm: Option<bool>,
+    /// Key bindings per scope, mapping key names to the names of actions.
+    #[cfg_attr(
+        not(any(feature = "tui-unix", feature = "tui-crossplatform")),
+        allow(dead_code)
+    )]
+    pub keys: BTreeMap<String, BTreeMap<String, String>>,
+    /// Colors overriding the ones of the theme, by name.
+    #[cfg_attr(
+        not(any(feature = "tui-unix", feature = "tui-crossplatform")),
+        allow(dead_code)
+    )]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// The path at which the configuration file is expected, if the configuration directory is known.
+    pub fn path() -> Option<PathBuf> {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .map(|dir| dir.join("dua").join("config.toml"))
+    }
+
+    /// Load the configuration file, or return an empty configuration if there is none.
+    pub fn load() -> Result<Self> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::from_toml(&content).with_context(|| {
+                format!("Could not parse configuration file at '{}'", path.display())
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| {
+                format!("Could not read configuration file at '{}'", path.display())
+            }),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Set all values in `args` which were not explicitly given on the command-line, as seen by `matches`.
+    ///
+    /// Flags are also left alone if the flag turning them off was given.
+    pub fn apply_to(&self, args: &mut Args, matches: &ArgMatches) {
+        fn set<T>(matches: &ArgMatches, id: &str, target: &mut T, value: Option<T>) {
+            let negation = NEGATED_FLAGS
+                .iter()
+                .find(|(flag, _)| *flag == id)
+                .map(|(_, negation)| *negation);
+            let is_given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+            if let Some(value) = value {
+                if !is_given(id) && !negation.is_some_and(is_given) {
+                    *target = value;
+                }
+            }
+        }
+        set(matches, "format", &mut args.format, self.format);
+        set(matches, "threads", &mut args.threads, self.threads);
+        set(
+            matches,
+            "ignore_dirs",
+            &mut args.ignore_dirs,
+            self.ignore_dirs.clone(),
+        );
+        set(
+            matches,
+            "apparent_size",
+            &mut args.apparent_size,
+            self.apparent_size,
+        );
+        set(
+            matches,
+            "count_hard_links",
+            &mut args.count_hard_links,
+            self.count_hard_links,
+        );
+        set(
+            matches,
+            "no_directory_sizes",
+            &mut args.no_directory_sizes,
+            self.no_directory_sizes,
+        );
+        set(
+            matches,
+            "stay_on_filesystem",
+            &mut args.stay_on_filesystem,
+            self.stay_on_filesystem,
+        );
+        set(matches, "sort", &mut args.sort, self.sort.map(Some));
+        set(matches, "byte_vis", &mut args.byte_vis, self.byte_vis);
+        set(matches, "theme", &mut args.theme, self.theme);
+        set(matches, "ls_colors", &mut args.ls_colors, self.ls_colors);
+        set(matches, "color", &mut args.color, self.color);
+        set(matches, "hyperlinks", &mut args.hyperlinks, self.hyperlinks);
+        set(matches, "no_confirm", &mut args.no_confirm, self.no_confirm);
+    }
+}
+
+/// Parse the command-line `args`, preceded by the ones in the `DUA_OPTS` environment variable,
+/// and use the configuration file for all values that remain unset.
+///
+/// The configuration is returned as well for all settings that have no command-line equivalent.
+pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<(Args, Config)> {
+    let mut args: Vec<_> = args.into_iter().collect();
+    if let Some(options) = std::env::var_os(OPTIONS_VARIABLE) {
+        let options = split_options(&options.to_string_lossy())
+            .with_context(|| format!("Could not parse options in {}", OPTIONS_VARIABLE))?;
+        let insert_at = 1.min(args.len());
+        args.splice(insert_at..insert_at, options);
+    }
+
+    let matches = command().get_matches_from(args);
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let config = Config::load()?;
+    config.apply_to(&mut args, &matches);
+    Ok((args, config))
+}
+
+/// The command-line interface of [`Args`], along with flags to turn off the ones set in the configuration file.
+fn command() -> clap::Command {
+    NEGATED_FLAGS
+        .iter()
+        .fold(Args::command(), |cmd, (flag, negation)| {
+            cmd.arg(
+                Arg::new(*negation)
+                    .long(*negation)
+                    .action(ArgAction::SetTrue)
+                    .overrides_with(*flag)
+                    .help(format!(
+                        "Turn off --{} if it is set in the configuration file or DUA_OPTS",
+                        flag.replace('_', "-")
+                    )),
+            )
+        })
+}
+
+/// Split `options` into words like a POSIX shell would, without expanding anything.
+///
+/// Words are separated by whitespace, which is retained within single or double quotes and after a backslash.
+fn split_options(options: &str) -> Result<Vec<OsString>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = options.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word).into());
+                    in_word = false;
+                }
+                continue;
+            }
+            '\'' => loop {
+                match chars.next() {
+                    Some('\'') => break,
+                    Some(c) => word.push(c),
+                    None => bail!("Missing closing single quote"),
+                }
+            },
+            '"' => loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                        Some(c) => {
+                            word.push('\\');
+                            word.push(c);
+                        }
+                        None => bail!("Missing closing double quote"),
+                    },
+                    Some(c) => word.push(c),
+                    None => bail!("Missing closing double quote"),
+                }
+            },
+            '\\' => match chars.next() {
+                Some(c) => word.push(c),
+                None => bail!("Nothing to escape after the trailing backslash"),
+            },
+            c => word.push(c),
+        }
+        in_word = true;
+    }
+    if in_word {
+        words.push(word.into());
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_keys_are_named_like_long_options() -> Result<()> {
+        let config = Config::from_toml(
+            r#"
+            format = "gib"
+            threads = 2
+            ignore-dirs = ["/mnt/backup"]
+            apparent-size = true
+            no-directory-sizes = true
+            stay-on-filesystem = true
+            sort = "size-ascending"
+            byte-vis = "long-bar"
+            theme = "high-contrast"
+
+            [colors]
+            directory = "blue"
+            "#,
+        )?;
+        assert_eq!(
+            config,
+            Config {
+                format: Some(ByteFormat::Gib),
+                threads: Some(2),
+                ignore_dirs: Some(vec!["/mnt/backup".into()]),
+                apparent_size: Some(true),
+                count_hard_links: None,
+                no_directory_sizes: Some(true),
+                stay_on_filesystem: Some(true),
+                sort: Some(SortMode::SizeAscending),
+                byte_vis: Some(ByteVisualization::LongBar),
+                theme: Some(Theme::HighContrast),
+                ls_colors: None,
+                color: None,
+                hyperlinks: None,
+                no_confirm: None,
+                keys: Default::default(),
+                colors: [("directory".into(), "blue".into())]
+                    .iter()
+                    .cloned()
+                    .collect(),
+            }
+        );
+        assert!(
+            Config::from_toml("unknown = 1").is_err(),
+            "typos are reported instead of being ignored"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn options_are_split_like_a_shell_would() -> Result<()> {
+        assert_eq!(
+            split_options(
+                r#" -x  --ignore-dirs "/mnt/My Drive" '/mnt/a "b"' /mnt/c\ d "" "\"\x" "#
+            )?,
+            [
+                "-x",
+                "--ignore-dirs",
+                "/mnt/My Drive",
+                "/mnt/a \"b\"",
+                "/mnt/c d",
+                "",
+                "\"\\x"
+            ]
+            .iter()
+            .map(OsString::from)
+            .collect::<Vec<_>>()
+        );
+        for unbalanced in ["'/mnt/a", "\"/mnt/a", "/mnt/a\\"] {
+            assert!(split_options(unbalanced).is_err(), "{}", unbalanced);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn command_line_options_take_precedence() -> Result<()> {
+        let matches = command().try_get_matches_from(["dua", "-f", "bytes", "-t", "1"])?;
+        let mut args = Args::from_arg_matches(&matches)?;
+        Config {
+            format: Some(ByteFormat::Gib),
+            threads: Some(4),
+            apparent_size: Some(true),
+            ..Default::default()
+        }
+        .apply_to(&mut args, &matches);
+
+        assert_eq!(args.format, ByteFormat::Bytes, "given on the command-line");
+        assert_eq!(args.threads, 1, "given on the command-line");
+        assert!(args.apparent_size, "the configuration fills in the rest");
+        Ok(())
+    }
+
+    #[test]
+    fn flags_of_the_configuration_can_be_turned_off_on_the_command_line() -> Result<()> {
+        let config = Config {
+            apparent_size: Some(true),
+            no_confirm: Some(true),
+            ..Default::default()
+        };
+        let args_from = |cli: &[&str]| -> Result<Args> {
+            let matches = command().try_get_matches_from(cli)?;
+            let mut args = Args::from_arg_matches(&matches)?;
+            config.apply_to(&mut args, &matches);
+            Ok(args)
+        };
+
+        let args = args_from(&["dua", "--no-apparent-size", "--confirm"])?;
+        assert!(
+            !args.apparent_size && !args.no_confirm,
+            "turned off for this run"
+        );
+
+        let args = args_from(&["dua", "--no-apparent-size", "-A"])?;
+        assert!(args.apparent_size, "the last flag wins");
+        let args = args_from(&["dua", "-A", "--no-apparent-size"])?;
+        assert!(!args.apparent_size, "the last flag wins");
+        Ok(())
+    }
+
+    #[test]
+    fn options_can_be_repeated_to_override_the_ones_in_dua_opts() -> Result<()> {
+        let matches =
+            command().try_get_matches_from(["dua", "-f", "gib", "-A", "-f", "bytes", "-A"])?;
+        let args = Args::from_arg_matches(&matches)?;
+        assert_eq!(args.format, ByteFormat::Bytes, "the last one wins");
+        assert!(args.apparent_size);
+
+        let matches = command().try_get_matches_from(["dua", "-i", "/a", "-i", "/b"])?;
+        let args = Args::from_arg_matches(&matches)?;
+        assert_eq!(
+            args.ignore_dirs,
+            vec![PathBuf::from("/a"), PathBuf::from("/b")],
+            "options taking multiple values still collect all of them"
+        );
+        Ok(())
+    }
+}
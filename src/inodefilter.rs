@@ -5,32 +5,40 @@ pub struct InodeFilter {
     inner: HashMap<(u64, u64), u64>,
 }
 
-impl InodeFilter {
-    #[cfg(unix)]
-    pub fn add(&mut self, metadata: &std::fs::Metadata) -> bool {
-        use std::os::unix::fs::MetadataExt;
+/// Return the device and inode numbers identifying the file with `metadata`, along with the amount of its links.
+#[cfg(unix)]
+pub fn dev_inode_nlinks(metadata: &std::fs::Metadata) -> Option<((u64, u64), u64)> {
+    use std::os::unix::fs::MetadataExt;
 
-        self.add_dev_inode((metadata.dev(), metadata.ino()), metadata.nlink())
-    }
+    Some(((metadata.dev(), metadata.ino()), metadata.nlink()))
+}
 
-    #[cfg(windows)]
-    pub fn add(&mut self, metadata: &std::fs::Metadata) -> bool {
-        use std::os::windows::fs::MetadataExt;
+#[cfg(windows)]
+pub fn dev_inode_nlinks(metadata: &std::fs::Metadata) -> Option<((u64, u64), u64)> {
+    use std::os::windows::fs::MetadataExt;
 
-        if let (Some(dev), Some(inode), Some(nlinks)) = (
-            metadata.volume_serial_number(),
-            metadata.file_index(),
-            metadata.number_of_links(),
-        ) {
-            self.add_dev_inode((dev as u64, inode), nlinks as u64)
-        } else {
-            true
-        }
+    if let (Some(dev), Some(inode), Some(nlinks)) = (
+        metadata.volume_serial_number(),
+        metadata.file_index(),
+        metadata.number_of_links(),
+    ) {
+        Some(((dev as u64, inode), nlinks as u64))
+    } else {
+        None
     }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn dev_inode_nlinks(_metadata: &std::fs::Metadata) -> Option<((u64, u64), u64)> {
+    None
+}
 
-    #[cfg(not(any(unix, windows)))]
+impl InodeFilter {
     pub fn add(&mut self, metadata: &std::fs::Metadata) -> bool {
-        true
+        match dev_inode_nlinks(metadata) {
+            Some((dev_inode, nlinks)) => self.add_dev_inode(dev_inode, nlinks),
+            None => true,
+        }
     }
 
     pub fn add_dev_inode(&mut self, dev_inode: (u64, u64), nlinks: u64) -> bool {
@@ -0,0 +1,602 @@
+use anyhow::{anyhow, bail, Context, Result};
+use crosstermion::input::Key;
+use itertools::Itertools;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Where a key binding is effective.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Scope {
+    /// Keys that work no matter which pane has focus.
+    Global,
+    /// Keys for the main pane with all entries.
+    Main,
+    /// Keys for the pane with all marked entries.
+    Mark,
+    /// Keys for the help pane.
+    Help,
+    /// Keys for the treemap pane.
+    Treemap,
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "global" => Scope::Global,
+            "main" => Scope::Main,
+            "mark" => Scope::Mark,
+            "help" => Scope::Help,
+            "treemap" => Scope::Treemap,
+            _ => {
+                bail!("Unknown key scope '{s}', expected one of 'global', 'main', 'mark', 'help' or 'treemap'")
+            }
+        })
+    }
+}
+
+/// The sections of the help pane, in the order they are shown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Section {
+    PaneControl,
+    Navigation,
+    Display,
+    EntryOperations,
+    MarkPane,
+    Treemap,
+    ApplicationControl,
+}
+
+impl Section {
+    const ALL: &'static [Section] = &[
+        Section::PaneControl,
+        Section::Navigation,
+        Section::Display,
+        Section::EntryOperations,
+        Section::MarkPane,
+        Section::Treemap,
+        Section::ApplicationControl,
+    ];
+
+    fn title(self) -> &'static str {
+        use Section::*;
+        match self {
+            PaneControl => "Keys for pane control",
+            Navigation => "Keys for Navigation",
+            Display => "Keys for display",
+            EntryOperations => "Keys for entry operations",
+            MarkPane => "Keys in the Mark pane",
+            Treemap => "Keys in the Treemap pane",
+            ApplicationControl => "Keys for application control",
+        }
+    }
+
+    /// The scope whose bindings are shown for the actions of this section.
+    fn scope(self) -> Scope {
+        use Section::*;
+        match self {
+            PaneControl | ApplicationControl => Scope::Global,
+            Navigation | Display | EntryOperations => Scope::Main,
+            MarkPane => Scope::Mark,
+            Treemap => Scope::Treemap,
+        }
+    }
+
+    /// Actions of other sections which are shown in this one as well, as they are bound in its scope.
+    fn shared_actions(self) -> &'static [Action] {
+        match self {
+            Section::Treemap => &[Action::EnterNode, Action::ExitNode],
+            _ => &[],
+        }
+    }
+}
+
+macro_rules! actions {
+    ($($(#[$attr:meta])* $variant:ident => $name:literal, $section:ident, $description:literal $(, $second_line:literal)?;)*) => {
+        /// All named actions that keys can be bound to.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum Action {
+            $($(#[$attr])* $variant,)*
+        }
+
+        impl Action {
+            /// All actions, in the order they are shown in the help pane.
+            const ALL: &'static [Action] = &[$($(#[$attr])* Action::$variant,)*];
+
+            /// The name of the action as used in the configuration file.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $($(#[$attr])* Action::$variant => $name,)*
+                }
+            }
+
+            fn section(self) -> Section {
+                match self {
+                    $($(#[$attr])* Action::$variant => Section::$section,)*
+                }
+            }
+
+            /// A description of the action, along with an optional second line.
+            pub fn description(self) -> (&'static str, Option<&'static str>) {
+                match self {
+                    $($(#[$attr])* Action::$variant => ($description, None$(.or(Some($second_line)))?),)*
+                }
+            }
+        }
+    };
+}
+
+actions! {
+    Quit => "quit", PaneControl, "Close the current pane. Closes the program if no", "pane is open";
+    CycleFocus => "cycle-focus", PaneControl, "Cycle between all open panes.", "Activate 'Marked Items' pane to delete selected files.";
+    ToggleHelp => "toggle-help", PaneControl, "Show or hide the help pane";
+    ToggleTreemap => "toggle-treemap", PaneControl, "Show or hide the treemap of the current directory";
+    Down => "down", Navigation, "move down an entry";
+    Up => "up", Navigation, "move up an entry";
+    EnterNode => "descend", Navigation, "descent into the selected directory", "In the tree view, expand it or move to its first entry";
+    ExitNode => "ascend", Navigation, "ascent one level into the parent directory", "In the tree view, collapse it or move to its parent";
+    PageDown => "page-down", Navigation, "move down 10 entries at once";
+    PageUp => "page-up", Navigation, "move up 10 entries at once";
+    SetBookmark => "set-bookmark", Navigation, "Bookmark the selected entry under the name of the next key";
+    JumpToBookmark => "jump-to-bookmark", Navigation, "Jump to the entry bookmarked under the name of the next key";
+    HistoryBack => "history-back", Navigation, "Go back to the previously visited directory";
+    HistoryForward => "history-forward", Navigation, "Go forward again to the directory visited next", "Ctrl + i can't be told apart from <Tab> in terminals";
+    GoToPath => "go-to-path", Navigation, "Go to a path typed relative to the scanned paths, or absolute", "<Tab> completes the names of entries";
+    ToTop => "top", Navigation, "Move to the top of the entries list";
+    ToBottom => "bottom", Navigation, "Move to the bottom of the entries list";
+    Search => "search", Navigation, "Search entries in the current directory by name", "<Tab> switches between substring, glob and regex";
+    SearchTree => "search-tree", Navigation, "Search all entries by name";
+    NextMatch => "next-match", Navigation, "Select the next match of the search";
+    PreviousMatch => "previous-match", Navigation, "Select the previous match of the search";
+    ToggleSortBySize => "sort-by-size", Display, "sort by size, or toggle between ascending/descending";
+    CycleSorting => "cycle-sort", Display, "cycle sorting by size, name, entry count, mtime and extension";
+    ReverseSorting => "reverse-sort", Display, "reverse the current sort order";
+    ToggleSizeKind => "toggle-size-kind", Display, "toggle between disk usage and apparent size", "It drives sorting, bars and totals.";
+    ToggleSizeRatio => "toggle-size-ratio", Display, "show or hide the ratio of disk usage to apparent size", "Sparse and compressed files have a ratio below 1.";
+    CycleByteVisualization => "cycle-byte-vis", Display, "cycle through percentage display and bar options";
+    ToggleTreeView => "toggle-tree-view", Display, "toggle between a list of entries and an expandable tree";
+    ToggleColumns => "toggle-columns", Display, "show or hide the parent and the selected directory in columns";
+    OpenEntry => "open", EntryOperations, "Open the entry with the associated program";
+    ToggleMarkAndAdvance => "toggle-mark-and-advance", EntryOperations, "Toggle the currently selected entry and move down";
+    MarkAndAdvance => "mark-and-advance", EntryOperations, "Mark the currently selected entry for deletion and move down";
+    ToggleMark => "toggle-mark", EntryOperations, "Toggle the currently selected entry";
+    ToggleAllMarks => "toggle-all-marks", EntryOperations, "Toggle all entries, which inverts their marks";
+    MarkAll => "mark-all", EntryOperations, "Mark all entries, or unmark them if all are marked";
+    VisualRange => "visual-range", EntryOperations, "Select a range of entries by moving, then mark it", "Press again or use any mark key to mark it, <Esc> cancels";
+    UnmarkDirectory => "unmark-directory", EntryOperations, "Unmark all entries below the current directory";
+    RemoveMark => "remove-mark", MarkPane, "Remove the selected entry from the list";
+    RemoveAllMarks => "remove-all-marks", MarkPane, "Remove all entries from the list";
+    DeleteMarked => "delete-marked", MarkPane, "Permanently delete all marked entries, once confirmed unless --no-confirm is set", "This operation cannot be undone! <Esc> cancels it while it runs";
+    WriteScript => "write-script", MarkPane, "Write a shell script deleting all marked entries for review", "It trashes them instead if that was asked for last, and goes to the file given with --dry-run, or to dua-deletion.sh, unless it exists already";
+    #[cfg(feature = "trash-move")]
+    TrashMarked => "trash-marked", MarkPane, "Move all marked entries to the trash bin", "The entries can be restored from the trash bin";
+    SelectLeft => "select-left", Treemap, "Select the rectangle on the left";
+    SelectRight => "select-right", Treemap, "Select the rectangle on the right";
+    SelectAbove => "select-above", Treemap, "Select the rectangle above";
+    SelectBelow => "select-below", Treemap, "Select the rectangle below";
+    CommandLine => "command-line", ApplicationControl, "Type a command like 'mark *.log', 'mark-tree *.core >100MB older 30d',", "'sort mtime', 'filter >1G', 'export marks.txt', 'import marks.txt', 'cd <path>' or 'rescan'";
+    Exit => "exit-program", ApplicationControl, "close the application. No questions asked!";
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Action::ALL
+            .iter()
+            .find(|action| action.name() == s)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown action '{s}'"))
+    }
+}
+
+/// A key as it can be bound to an action, which can be parsed from and displayed as text.
+#[derive(Debug, Copy, Clone)]
+pub struct KeySpec(pub Key);
+
+impl PartialEq for KeySpec {
+    fn eq(&self, other: &Self) -> bool {
+        use Key::*;
+        match (self.0, other.0) {
+            (F(lhs), F(rhs)) => lhs == rhs,
+            (Char(lhs), Char(rhs)) | (Alt(lhs), Alt(rhs)) | (Ctrl(lhs), Ctrl(rhs)) => lhs == rhs,
+            (lhs, rhs) => std::mem::discriminant(&lhs) == std::mem::discriminant(&rhs),
+        }
+    }
+}
+
+impl FromStr for KeySpec {
+    type Err = anyhow::Error;
+
+    /// Parse keys like `j`, `O`, `ctrl-d`, `alt-x`, `<Down>`, `page-up` or `f1`.
+    fn from_str(s: &str) -> Result<Self> {
+        use Key::*;
+        fn single_char(s: &str) -> Option<char> {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        }
+        if let Some(c) = single_char(s) {
+            return Ok(KeySpec(Char(c)));
+        }
+        let trimmed = s.trim_start_matches('<').trim_end_matches('>');
+        if let Some((modifier, c)) = trimmed.split_once(['-', '+']) {
+            let modifier = modifier.trim().to_ascii_lowercase();
+            if let (Some(c), "ctrl" | "alt") = (single_char(c.trim()), modifier.as_str()) {
+                return Ok(KeySpec(if modifier == "ctrl" { Ctrl(c) } else { Alt(c) }));
+            }
+        }
+        let name = trimmed.to_ascii_lowercase().replace(['_', ' '], "-");
+        let key = match name.as_str() {
+            "space" => Char(' '),
+            "enter" | "return" => Char('\n'),
+            "tab" => Char('\t'),
+            "esc" | "escape" => Esc,
+            "backspace" => Backspace,
+            "left" => Left,
+            "right" => Right,
+            "up" => Up,
+            "down" => Down,
+            "home" => Home,
+            "end" => End,
+            "pageup" | "page-up" => PageUp,
+            "pagedown" | "page-down" => PageDown,
+            "backtab" | "shift-tab" => BackTab,
+            "delete" | "del" => Delete,
+            "insert" => Insert,
+            _ => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) => F(n),
+                None => bail!("Unknown key '{s}'"),
+            },
+        };
+        Ok(KeySpec(key))
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Key::*;
+        match self.0 {
+            Char(' ') => f.write_str("<Space>"),
+            Char('\n') => f.write_str("<Enter>"),
+            Char('\t') => f.write_str("<Tab>"),
+            Char(c) => write!(f, "{c}"),
+            Ctrl(c) => write!(f, "Ctrl + {c}"),
+            Alt(c) => write!(f, "Alt + {c}"),
+            F(n) => write!(f, "<F{n}>"),
+            Backspace => f.write_str("<Backspace>"),
+            Left => f.write_str("<Left>"),
+            Right => f.write_str("<Right>"),
+            Up => f.write_str("<Up>"),
+            Down => f.write_str("<Down>"),
+            Home => f.write_str("<Home>"),
+            End => f.write_str("<End>"),
+            PageUp => f.write_str("<Page Up>"),
+            PageDown => f.write_str("<Page Down>"),
+            BackTab => f.write_str("<Shift + Tab>"),
+            Delete => f.write_str("<Delete>"),
+            Insert => f.write_str("<Insert>"),
+            Null => f.write_str("<Null>"),
+            Esc => f.write_str("<Esc>"),
+        }
+    }
+}
+
+/// The title of a help section along with its actions and the names of the keys bound to them.
+pub type HelpSection = (&'static str, Vec<(Vec<String>, Action)>);
+
+/// A table mapping keys to named actions, per scope.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    bindings: Vec<(Scope, KeySpec, Action)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use Key::*;
+        use Scope::*;
+        let navigation = |scope| {
+            [
+                (scope, Char('j'), Action::Down),
+                (scope, Key::Down, Action::Down),
+                (scope, Char('k'), Action::Up),
+                (scope, Key::Up, Action::Up),
+                (scope, Ctrl('d'), Action::PageDown),
+                (scope, Key::PageDown, Action::PageDown),
+                (scope, Ctrl('u'), Action::PageUp),
+                (scope, Key::PageUp, Action::PageUp),
+                (scope, Char('H'), Action::ToTop),
+                (scope, Char('G'), Action::ToBottom),
+            ]
+        };
+        let bindings = IntoIterator::into_iter([
+            (Global, Char('q'), Action::Quit),
+            (Global, Esc, Action::Quit),
+            (Global, Char('\t'), Action::CycleFocus),
+            (Global, Char('?'), Action::ToggleHelp),
+            (Global, Char('T'), Action::ToggleTreemap),
+            (Global, Ctrl('c'), Action::Exit),
+        ])
+        .chain(navigation(Main))
+        .chain([
+            (Main, Char('/'), Action::Search),
+            (Main, Char('f'), Action::SearchTree),
+            (Main, Char('n'), Action::NextMatch),
+            (Main, Char('N'), Action::PreviousMatch),
+            (Main, Home, Action::ToTop),
+            (Main, End, Action::ToBottom),
+            (Main, Char('o'), Action::EnterNode),
+            (Main, Char('l'), Action::EnterNode),
+            (Main, Char('\n'), Action::EnterNode),
+            (Main, Right, Action::EnterNode),
+            (Main, Char('u'), Action::ExitNode),
+            (Main, Char('h'), Action::ExitNode),
+            (Main, Left, Action::ExitNode),
+            (Main, Backspace, Action::ExitNode),
+            (Main, Char('m'), Action::SetBookmark),
+            (Main, Char('\''), Action::JumpToBookmark),
+            (Main, Char('`'), Action::JumpToBookmark),
+            (Main, Ctrl('o'), Action::HistoryBack),
+            (Main, Ctrl('n'), Action::HistoryForward),
+            (Main, Char('c'), Action::GoToPath),
+            (Main, Char(':'), Action::CommandLine),
+            (Main, Char('s'), Action::ToggleSortBySize),
+            (Main, Char('S'), Action::CycleSorting),
+            (Main, Char('R'), Action::ReverseSorting),
+            (Main, Char('A'), Action::ToggleSizeKind),
+            (Main, Char('r'), Action::ToggleSizeRatio),
+            (Main, Char('g'), Action::CycleByteVisualization),
+            (Main, Char('t'), Action::ToggleTreeView),
+            (Main, Char('C'), Action::ToggleColumns),
+            (Main, Char('O'), Action::OpenEntry),
+            (Main, Char('d'), Action::ToggleMarkAndAdvance),
+            (Main, Char('x'), Action::MarkAndAdvance),
+            (Main, Char(' '), Action::ToggleMark),
+            (Main, Char('a'), Action::ToggleAllMarks),
+            (Main, Char('M'), Action::MarkAll),
+            (Main, Char('V'), Action::VisualRange),
+            (Main, Char('U'), Action::UnmarkDirectory),
+        ])
+        .chain(navigation(Mark))
+        .chain([
+            (Mark, Char('x'), Action::RemoveMark),
+            (Mark, Char('d'), Action::RemoveMark),
+            (Mark, Char(' '), Action::RemoveMark),
+            (Mark, Char('a'), Action::RemoveAllMarks),
+            (Mark, Ctrl('r'), Action::DeleteMarked),
+            (Mark, Char('w'), Action::WriteScript),
+            #[cfg(feature = "trash-move")]
+            (Mark, Ctrl('t'), Action::TrashMarked),
+        ])
+        .chain(navigation(Help))
+        .chain([
+            (Treemap, Char('h'), Action::SelectLeft),
+            (Treemap, Left, Action::SelectLeft),
+            (Treemap, Char('l'), Action::SelectRight),
+            (Treemap, Right, Action::SelectRight),
+            (Treemap, Char('k'), Action::SelectAbove),
+            (Treemap, Up, Action::SelectAbove),
+            (Treemap, Char('j'), Action::SelectBelow),
+            (Treemap, Down, Action::SelectBelow),
+            (Treemap, Char('o'), Action::EnterNode),
+            (Treemap, Char('\n'), Action::EnterNode),
+            (Treemap, Char('u'), Action::ExitNode),
+            (Treemap, Backspace, Action::ExitNode),
+        ])
+        .map(|(scope, key, action)| (scope, KeySpec(key), action))
+        .collect();
+        KeyBindings { bindings }
+    }
+}
+
+impl KeyBindings {
+    /// Create the default bindings, overridden by `config` which maps scopes to keys and action names.
+    pub fn from_config(config: &BTreeMap<String, BTreeMap<String, String>>) -> Result<Self> {
+        let mut bindings = Self::default();
+        for (scope, keys) in config {
+            let scope: Scope = scope.parse()?;
+            for (key, action) in keys {
+                let key: KeySpec = key
+                    .parse()
+                    .with_context(|| format!("Could not bind key in scope '{scope:?}'"))?;
+                let action: Action = action
+                    .parse()
+                    .with_context(|| format!("Could not bind key '{key}'"))?;
+                bindings.bind(scope, key, action);
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// Bind `key` to `action` in `scope`, replacing the action previously bound to it.
+    pub fn bind(&mut self, scope: Scope, key: KeySpec, action: Action) {
+        self.bindings
+            .retain(|(s, k, _)| !(*s == scope && *k == key));
+        self.bindings.push((scope, key, action));
+    }
+
+    /// Return the action bound to `key` in `scope`, if there is one.
+    pub fn action(&self, scope: Scope, key: Key) -> Option<Action> {
+        let key = KeySpec(key);
+        self.bindings
+            .iter()
+            .find(|(s, k, _)| *s == scope && *k == key)
+            .map(|(_, _, action)| *action)
+    }
+
+    /// Return all keys bound to `action` in `scope`, in the order they were bound.
+    pub fn keys(&self, scope: Scope, action: Action) -> impl Iterator<Item = KeySpec> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(s, _, a)| *s == scope && *a == action)
+            .map(|(_, k, _)| *k)
+    }
+
+    /// Return the first key bound to `action` in `scope` for display, or `?` if it is unbound.
+    pub fn key_name(&self, scope: Scope, action: Action) -> String {
+        self.keys(scope, action)
+            .next()
+            .map_or_else(|| "?".into(), |k| k.to_string())
+    }
+
+    /// Return a hint like ` name = a,b | other = c` with all keys bound to each of the named `actions` in `scope`,
+    /// leaving out actions that are unbound.
+    pub fn hint(&self, scope: Scope, actions: &[(&str, Action)]) -> String {
+        actions
+            .iter()
+            .filter_map(|(name, action)| {
+                let keys = self.keys(scope, *action).join(",");
+                (!keys.is_empty()).then(|| format!(" {name} = {keys}"))
+            })
+            .join(" |")
+    }
+
+    /// Return a hint of the keys moving through the list in `scope`, along with entering and leaving directories
+    /// if `with_nodes` is set.
+    pub fn navigation_hint(&self, scope: Scope, with_nodes: bool) -> String {
+        let moves = format!(
+            "⇊ = {}|↓ = {}|⇈ = {}|↑ = {} ",
+            self.key_name(scope, Action::PageDown),
+            self.key_name(scope, Action::Down),
+            self.key_name(scope, Action::PageUp),
+            self.key_name(scope, Action::Up)
+        );
+        if with_nodes {
+            format!(
+                " . = {}|.. = {} ── {}",
+                self.key_name(scope, Action::EnterNode),
+                self.key_name(scope, Action::ExitNode),
+                moves
+            )
+        } else {
+            format!(" {moves}")
+        }
+    }
+
+    /// Return the sections of the help pane, each with the names of all keys bound to its actions.
+    pub fn help(&self) -> Vec<HelpSection> {
+        Section::ALL
+            .iter()
+            .map(|section| {
+                let actions = Action::ALL
+                    .iter()
+                    .filter(|action| action.section() == *section)
+                    .chain(section.shared_actions())
+                    .map(|action| {
+                        let keys = self
+                            .keys(section.scope(), *action)
+                            .map(|k| k.to_string())
+                            .collect();
+                        (keys, *action)
+                    })
+                    .collect();
+                (section.title(), actions)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(s: &str) -> Key {
+        s.parse::<KeySpec>().expect("valid key").0
+    }
+
+    #[test]
+    fn keys_can_be_parsed_and_displayed() {
+        for (input, expected) in [
+            ("j", "j"),
+            ("O", "O"),
+            ("ctrl-d", "Ctrl + d"),
+            ("Ctrl-D", "Ctrl + D"),
+            ("alt-x", "Alt + x"),
+            ("<Down>", "<Down>"),
+            ("page-up", "<Page Up>"),
+            ("space", "<Space>"),
+            ("enter", "<Enter>"),
+            ("f2", "<F2>"),
+        ] {
+            assert_eq!(KeySpec(key(input)).to_string(), expected, "{input}");
+        }
+        assert!("ctrl-".parse::<KeySpec>().is_err());
+        assert!("hyper-x".parse::<KeySpec>().is_err());
+    }
+
+    #[test]
+    fn bindings_can_be_overridden_from_configuration() -> Result<()> {
+        let config = [(
+            "main".to_string(),
+            [
+                ("ctrl-x".to_string(), "mark-and-advance".to_string()),
+                ("j".to_string(), "up".to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect(),
+        )]
+        .iter()
+        .cloned()
+        .collect();
+        let bindings = KeyBindings::from_config(&config)?;
+        assert_eq!(
+            bindings.action(Scope::Main, Key::Ctrl('x')),
+            Some(Action::MarkAndAdvance)
+        );
+        assert_eq!(
+            bindings.action(Scope::Main, Key::Char('x')),
+            Some(Action::MarkAndAdvance),
+            "previous bindings remain"
+        );
+        assert_eq!(
+            bindings.action(Scope::Main, Key::Char('j')),
+            Some(Action::Up),
+            "keys can be rebound"
+        );
+        assert_eq!(
+            bindings.action(Scope::Mark, Key::Char('j')),
+            Some(Action::Down),
+            "other scopes are unaffected"
+        );
+
+        let help = bindings.help();
+        let (_, navigation) = &help[1];
+        assert!(
+            navigation
+                .iter()
+                .any(|(keys, action)| *action == Action::Up && keys.contains(&"j".to_string())),
+            "the help reflects the actual bindings"
+        );
+        assert_eq!(
+            bindings.hint(
+                Scope::Main,
+                &[
+                    ("mark", Action::MarkAndAdvance),
+                    ("bogus", Action::SelectLeft)
+                ]
+            ),
+            " mark = x,Ctrl + x",
+            "hints reflect the actual bindings, and leave out unbound actions"
+        );
+        assert_eq!(
+            bindings.navigation_hint(Scope::Main, true),
+            " . = o|.. = u ── ⇊ = Ctrl + d|↓ = <Down>|⇈ = Ctrl + u|↑ = k "
+        );
+
+        let unknown_action = [(
+            "main".to_string(),
+            [("x".to_string(), "explode".to_string())]
+                .iter()
+                .cloned()
+                .collect(),
+        )]
+        .iter()
+        .cloned()
+        .collect();
+        assert!(KeyBindings::from_config(&unknown_action).is_err());
+        Ok(())
+    }
+}
@@ -100,6 +100,10 @@ impl DisplayByteVisualization {
 pub struct DisplayOptions {
     pub byte_format: ByteFormat,
     pub byte_vis: ByteVisualization,
+    /// If set, show the ratio between the size on disk and the apparent size of each entry
+    pub show_size_ratio: bool,
+    /// If set, show the entries of the parent directory and of the selected directory next to the current ones
+    pub show_columns: bool,
 }
 
 impl From<WalkOptions> for DisplayOptions {
@@ -107,6 +111,8 @@ impl From<WalkOptions> for DisplayOptions {
         DisplayOptions {
             byte_format,
             byte_vis: ByteVisualization::default(),
+            show_size_ratio: false,
+            show_columns: false,
         }
     }
 }
@@ -0,0 +1,468 @@
+use crate::interactive::{
+    path_of, resolve_path,
+    widgets::{read_mark_list, MainWindow},
+    AppState, MarkEntryMode, MarkRule, Pattern, PatternKind, SizeFilter, SortMode,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use crosstermion::input::Key;
+use dua::{traverse::Traversal, ByteFormat, WalkOptions};
+use itertools::Itertools;
+use std::{fs, io::Write, path::Path, path::PathBuf, str::FromStr};
+
+/// The amount of command lines remembered to recall them.
+const MAX_HISTORY: usize = 100;
+/// The amount of entries that couldn't be imported to list by path.
+const MAX_REPORTED_MISSING: usize = 3;
+
+/// A command typed into the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Mark all entries of the current directory whose names match a glob.
+    Mark(String),
+    /// Mark all files below the current directory that match a rule, once confirmed.
+    MarkTree(MarkRule),
+    /// Change the order of entries.
+    Sort(SortMode),
+    /// Show only entries of a certain size, or all entries if unset.
+    Filter(Option<SizeFilter>),
+    /// Write all marked entries into a file, one per line along with their sizes.
+    Export(PathBuf),
+    /// Mark all entries listed in a file written by `Export`.
+    Import(PathBuf),
+    /// Traverse the scanned paths again.
+    Rescan,
+    /// Go to the given path.
+    Cd(String),
+}
+
+impl FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (name, args) = line
+            .split_once(char::is_whitespace)
+            .map_or((line, ""), |(name, args)| (name, args.trim()));
+        let required = |what: &str| {
+            if args.is_empty() {
+                Err(anyhow!("'{name}' needs {what}"))
+            } else {
+                Ok(args)
+            }
+        };
+        Ok(match name {
+            "mark" => Command::Mark(required("a glob like '*.log'")?.to_owned()),
+            "mark-tree" => {
+                Command::MarkTree(required("a rule like '*.core >100MB older 30d'")?.parse()?)
+            }
+            "sort" => Command::Sort(parse_sorting(required(
+                "one of 'size', 'name', 'count', 'mtime' or 'extension'",
+            )?)?),
+            "filter" => Command::Filter(match args {
+                "" => None,
+                args => Some(args.parse()?),
+            }),
+            "export" => Command::Export(required("a file to write to")?.into()),
+            "import" => Command::Import(required("a file to read from")?.into()),
+            "rescan" => Command::Rescan,
+            "cd" => Command::Cd(args.to_owned()),
+            "" => bail!("Type a command like 'sort name' or 'filter >1G'"),
+            _ => bail!("Unknown command '{name}'"),
+        })
+    }
+}
+
+/// Parse a property to sort by like `name`, optionally followed by `asc` or `desc`.
+fn parse_sorting(args: &str) -> Result<SortMode> {
+    use SortMode::*;
+    let mut words = args.split_whitespace();
+    let (property, direction) = (words.next().unwrap_or_default(), words.next());
+    let mut sorting = match property {
+        "size" => SizeDescending,
+        "name" => NameAscending,
+        "count" => CountDescending,
+        "mtime" => MTimeDescending,
+        "extension" | "ext" => ExtensionAscending,
+        _ => bail!("Can't sort by '{property}'"),
+    };
+    let is_ascending = matches!(
+        sorting,
+        NameAscending | ExtensionAscending | SizeAscending | CountAscending | MTimeAscending
+    );
+    match direction {
+        None => {}
+        Some("asc" | "ascending") if is_ascending => {}
+        Some("desc" | "descending") if !is_ascending => {}
+        Some("asc" | "ascending" | "desc" | "descending") => sorting.reverse(),
+        Some(other) => bail!("Unknown sort order '{other}', expected 'asc' or 'desc'"),
+    }
+    Ok(sorting)
+}
+
+/// A command being edited, along with previously executed ones to recall them.
+#[derive(Default, Debug, Clone)]
+pub struct CommandLine {
+    pub text: String,
+    pub is_editing: bool,
+    history: Vec<String>,
+    /// The position in `history` of the recalled command line, if one was recalled.
+    history_position: Option<usize>,
+}
+
+impl CommandLine {
+    fn start(&mut self) {
+        self.text.clear();
+        self.is_editing = true;
+        self.history_position = None;
+    }
+
+    fn finish(&mut self) -> String {
+        self.is_editing = false;
+        self.history_position = None;
+        let line = std::mem::take(&mut self.text);
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        line
+    }
+
+    /// Show the previous command line in the history, or the next one if `backwards` is `false`.
+    fn recall(&mut self, backwards: bool) {
+        let position = match (self.history_position, backwards) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(pos), true) => Some(pos.saturating_sub(1)),
+            (Some(pos), false) => Some(pos + 1).filter(|pos| *pos < self.history.len()),
+        };
+        self.history_position = position;
+        self.text = position
+            .and_then(|pos| self.history.get(pos).cloned())
+            .unwrap_or_default();
+    }
+}
+
+/// How the traversal was made, to make it again when rescanning.
+#[derive(Clone)]
+pub struct Scan {
+    pub walk_options: WalkOptions,
+    pub input_paths: Vec<PathBuf>,
+}
+
+impl AppState {
+    pub fn start_command_line(&mut self) {
+        self.command_line.start();
+    }
+
+    /// Edit the command line with `key` if it is being edited, and return `true` if the key was used for that.
+    pub fn process_command_line_input(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        format: ByteFormat,
+    ) -> bool {
+        let command_line = &mut self.command_line;
+        if !command_line.is_editing {
+            return false;
+        }
+        match key {
+            Key::Esc => command_line.is_editing = false,
+            Key::Char('\n') => {
+                let line = command_line.finish();
+                if let Err(err) = line
+                    .parse()
+                    .and_then(|command| self.execute(command, window, traversal, format))
+                {
+                    self.message = Some(format!("{err:#}"));
+                }
+            }
+            Key::Up => command_line.recall(true),
+            Key::Down => command_line.recall(false),
+            Key::Backspace if command_line.text.is_empty() => command_line.is_editing = false,
+            Key::Backspace => {
+                command_line.text.pop();
+            }
+            Key::Ctrl('u') => command_line.text.clear(),
+            Key::Char(c) => command_line.text.push(c),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn execute(
+        &mut self,
+        command: Command,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+        format: ByteFormat,
+    ) -> Result<()> {
+        match command {
+            Command::Mark(glob) => {
+                let pattern = Pattern::new(PatternKind::Glob, &glob)?;
+                let matches: Vec<_> = self
+                    .entries
+                    .iter()
+                    .filter(|e| pattern.is_match(&e.data.name))
+                    .map(|e| e.index)
+                    .collect();
+                for index in &matches {
+                    self.mark_entry_by_index(
+                        *index,
+                        MarkEntryMode::MarkForDeletion,
+                        window,
+                        traversal,
+                    );
+                }
+                self.message = Some(format!("Marked {} entries", matches.len()));
+            }
+            Command::MarkTree(rule) => self.preview_marks(&rule, format, traversal)?,
+            Command::Sort(sorting) => {
+                self.sorting = sorting;
+                self.entries = self.entries_at(&traversal.tree, self.root);
+            }
+            Command::Filter(filter) => {
+                self.filter = filter;
+                self.entries = self.entries_at(&traversal.tree, self.root);
+                if self
+                    .selected
+                    .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
+                    .is_none()
+                {
+                    self.selected = self.entries.first().map(|e| e.index);
+                }
+            }
+            Command::Export(path) => {
+                let pane = window
+                    .mark_pane
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("There are no marked entries to export"))?;
+                fs::File::create(&path)
+                    .map(std::io::BufWriter::new)
+                    .and_then(|mut out| {
+                        pane.write_list(&mut out)?;
+                        out.flush()
+                    })
+                    .with_context(|| format!("Could not export marks to '{}'", path.display()))?;
+                self.message = Some(format!(
+                    "Exported {} marked entries to '{}'",
+                    pane.marked().len(),
+                    path.display()
+                ));
+            }
+            Command::Import(path) => {
+                let list = fs::read_to_string(&path)
+                    .with_context(|| format!("Could not import marks from '{}'", path.display()))?;
+                self.import_marks(read_mark_list(&list), window, traversal);
+            }
+            Command::Rescan => self.request_rescan()?,
+            Command::Cd(path) => {
+                let index =
+                    resolve_path(traversal, Path::new(&path)).map_err(|err| anyhow!(err))?;
+                self.go_to_index(index, traversal);
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark the entries at `paths`, and report those that don't exist anymore.
+    fn import_marks(
+        &mut self,
+        paths: Vec<PathBuf>,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        let missing = self.mark_paths(&paths, window, traversal);
+        self.message = Some(format!("Marked {}", describe_marked(paths.len(), &missing)));
+    }
+
+    /// Mark the entries at `paths`, and return those of them that don't exist.
+    fn mark_paths(
+        &mut self,
+        paths: &[PathBuf],
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) -> Vec<String> {
+        let mut missing = Vec::new();
+        let mut pane = window.mark_pane.take().unwrap_or_default();
+        for path in paths {
+            let metadata = resolve_path(traversal, path)
+                .ok()
+                .filter(|index| *index != traversal.root_index)
+                .and_then(|index| {
+                    Some((
+                        index,
+                        path_of(&traversal.tree, index).symlink_metadata().ok()?,
+                    ))
+                });
+            match metadata {
+                Some((index, metadata)) => {
+                    pane = pane
+                        .toggle_index(index, &traversal.tree, metadata.is_dir(), false)
+                        .unwrap_or_default()
+                }
+                None => missing.push(path.display().to_string()),
+            }
+        }
+        window.mark_pane = Some(pane).filter(|pane| !pane.marked().is_empty());
+        missing
+    }
+
+    /// Request to traverse the scanned paths again, which happens once the event loop is left.
+    fn request_rescan(&mut self) -> Result<()> {
+        if self.deletion.is_some() {
+            bail!("Marked entries are being deleted, rescan once that is done");
+        }
+        if self.is_scanning {
+            bail!("The scanned paths are being traversed already");
+        }
+        if self.scan.is_none() {
+            bail!("There is nothing to rescan");
+        }
+        self.rescan_requested = true;
+        Ok(())
+    }
+
+    /// Forget everything that refers to entries of `traversal` before it is replaced, as their indices may be reused.
+    ///
+    /// Return the path of the directory that was shown and the paths of all marked entries, to find them again.
+    pub fn forget_traversal(
+        &mut self,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) -> (PathBuf, Vec<PathBuf>) {
+        self.forget_entries();
+        (
+            path_of(&traversal.tree, self.root),
+            take_marked_paths(window),
+        )
+    }
+
+    fn forget_entries(&mut self) {
+        self.bookmarks.clear();
+        self.named_bookmarks.clear();
+        self.history = Default::default();
+        self.expanded.clear();
+        self.search = None;
+        self.visual_anchor = None;
+        self.marks_preview = None;
+        self.deletion_request = None;
+    }
+
+    /// Show the directory at `root_path` unless `keep_root` is set, and mark the entries at `marked` paths again, after
+    /// `traversal` was made again. If `is_aborted` is set, the previous traversal was kept.
+    pub fn finish_rescan(
+        &mut self,
+        root_path: &Path,
+        mut marked: Vec<PathBuf>,
+        keep_root: bool,
+        is_aborted: bool,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        // Entries marked while rescanning are found by path as well, as an aborted rescan doesn't keep them.
+        self.forget_entries();
+        marked.extend(take_marked_paths(window));
+        let marked: Vec<_> = marked.into_iter().unique().collect();
+        let root = if keep_root && !is_aborted {
+            self.root
+        } else {
+            resolve_path(traversal, root_path).unwrap_or(traversal.root_index)
+        };
+        self.visit(root, traversal);
+
+        let missing = self.mark_paths(&marked, window, traversal);
+        let scanned = if is_aborted {
+            "Rescan was aborted"
+        } else {
+            "Rescanned"
+        };
+        self.message = Some(if marked.is_empty() {
+            scanned.into()
+        } else {
+            format!(
+                "{} and marked {}",
+                scanned,
+                describe_marked(marked.len(), &missing)
+            )
+        });
+    }
+}
+
+/// Remove all marks, and return the paths of the marked entries in the order they were marked.
+fn take_marked_paths(window: &mut MainWindow) -> Vec<PathBuf> {
+    window
+        .mark_pane
+        .take()
+        .map(|pane| {
+            pane.marked_sorted_by_index()
+                .into_iter()
+                .map(|(_, mark)| mark.path.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Describe how many of `num_paths` were marked, given the `missing` ones.
+fn describe_marked(num_paths: usize, missing: &[String]) -> String {
+    let num_marked = num_paths - missing.len();
+    match missing.len() {
+        0 => format!("{} entries", num_marked),
+        num_missing => format!(
+            "{} of {} entries, {} no longer exist: {}{}",
+            num_marked,
+            num_paths,
+            num_missing,
+            missing.iter().take(MAX_REPORTED_MISSING).join(", "),
+            if num_missing > MAX_REPORTED_MISSING {
+                format!(" and {} more", num_missing - MAX_REPORTED_MISSING)
+            } else {
+                String::new()
+            }
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed_with_their_arguments() {
+        assert_eq!(
+            "mark *.log".parse::<Command>().unwrap(),
+            Command::Mark("*.log".into())
+        );
+        assert_eq!(
+            " sort  mtime asc".parse::<Command>().unwrap(),
+            Command::Sort(SortMode::MTimeAscending)
+        );
+        assert_eq!(
+            "sort name".parse::<Command>().unwrap(),
+            Command::Sort(SortMode::NameAscending)
+        );
+        assert_eq!(
+            "filter >1G".parse::<Command>().unwrap(),
+            Command::Filter(Some(SizeFilter::Larger(1_000_000_000)))
+        );
+        assert_eq!("filter".parse::<Command>().unwrap(), Command::Filter(None));
+        assert_eq!("rescan".parse::<Command>().unwrap(), Command::Rescan);
+        for (line, error) in [
+            ("mark", "'mark' needs a glob like '*.log'"),
+            ("sort color", "Can't sort by 'color'"),
+            (
+                "filter 1G",
+                "Size filters start with '>' or '<', like '>1G'",
+            ),
+            ("explode", "Unknown command 'explode'"),
+        ] {
+            assert_eq!(
+                line.parse::<Command>().unwrap_err().to_string(),
+                error,
+                "{line}"
+            );
+        }
+    }
+}
@@ -1,55 +1,186 @@
 use crate::interactive::path_of;
-use dua::traverse::{EntryData, Tree, TreeIndex};
+use dua::traverse::{EntryData, SizeKind, Traversal, Tree, TreeIndex};
+pub use dua::SortMode;
 use itertools::Itertools;
 use petgraph::Direction;
+use std::{collections::BTreeSet, fmt, fs::Metadata, str::FromStr, time::Instant};
 use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Default, Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
-pub enum SortMode {
-    #[default]
-    SizeDescending,
-    SizeAscending,
+pub struct EntryDataBundle {
+    pub index: TreeIndex,
+    pub data: EntryData,
+    pub is_dir: bool,
+    pub exists: bool,
+    /// The metadata of the entry itself as of listing it, if it exists.
+    pub metadata: Option<Metadata>,
+    /// How deeply the entry is nested below the directory that is shown, which is 0 unless it is shown as tree.
+    pub depth: usize,
+}
+
+/// A condition on the size of entries to show only some of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeFilter {
+    Larger(u128),
+    Smaller(u128),
 }
 
-impl SortMode {
-    pub fn toggle_size(&mut self) {
-        use SortMode::*;
-        *self = match self {
-            SizeAscending => SizeDescending,
-            SizeDescending => SizeAscending,
+impl SizeFilter {
+    pub fn matches(self, size: u128) -> bool {
+        match self {
+            SizeFilter::Larger(bytes) => size > bytes,
+            SizeFilter::Smaller(bytes) => size < bytes,
         }
     }
 }
 
-pub struct EntryDataBundle {
-    pub index: TreeIndex,
-    pub data: EntryData,
-    pub is_dir: bool,
-    pub exists: bool,
+impl FromStr for SizeFilter {
+    type Err = anyhow::Error;
+
+    /// Parse filters like `>1G` or `< 500KiB`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (filter, size): (fn(u128) -> SizeFilter, _) = match s.trim() {
+            s if s.starts_with('>') => (SizeFilter::Larger, &s[1..]),
+            s if s.starts_with('<') => (SizeFilter::Smaller, &s[1..]),
+            _ => anyhow::bail!("Size filters start with '>' or '<', like '>1G'"),
+        };
+        let bytes = byte_unit::Byte::from_str(size.trim())
+            .map_err(|err| anyhow::anyhow!("Invalid size '{}': {}", size.trim(), err))?
+            .get_bytes();
+        Ok(filter(bytes))
+    }
 }
 
-pub fn sorted_entries(tree: &Tree, node_idx: TreeIndex, sorting: SortMode) -> Vec<EntryDataBundle> {
-    use SortMode::*;
+impl fmt::Display for SizeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (op, bytes) = match self {
+            SizeFilter::Larger(bytes) => ('>', bytes),
+            SizeFilter::Smaller(bytes) => ('<', bytes),
+        };
+        write!(f, "{}{}", op, dua::ByteFormat::Metric.display(*bytes))
+    }
+}
+
+pub fn sorted_entries(
+    tree: &Tree,
+    node_idx: TreeIndex,
+    sorting: SortMode,
+    size_kind: SizeKind,
+) -> Vec<EntryDataBundle> {
     tree.neighbors_directed(node_idx, Direction::Outgoing)
         .filter_map(|idx| {
             tree.node_weight(idx).map(|w| {
-                let p = path_of(tree, idx);
-                let pm = p.symlink_metadata();
+                let metadata = path_of(tree, idx).symlink_metadata().ok();
                 EntryDataBundle {
                     index: idx,
                     data: w.clone(),
-                    exists: pm.is_ok(),
-                    is_dir: pm.ok().map_or(false, |m| m.is_dir()),
+                    exists: metadata.is_some(),
+                    is_dir: metadata.as_ref().is_some_and(|m| m.is_dir()),
+                    metadata,
+                    depth: 0,
                 }
             })
         })
-        .sorted_by(|l, r| match sorting {
-            SizeDescending => r.data.size.cmp(&l.data.size),
-            SizeAscending => l.data.size.cmp(&r.data.size),
-        })
+        .sorted_by(|l, r| sorting.compare(&l.data.sortable(size_kind), &r.data.sortable(size_kind)))
         .collect()
 }
 
+/// The entries shown in the columns next to the ones of the current directory, which are only listed again once
+/// what they depend on changed.
+#[derive(Default)]
+pub struct Columns {
+    /// What the entries were listed for, if they were listed yet.
+    key: Option<ColumnsKey>,
+    /// The parent of the current directory, and its entries.
+    pub parent: Option<(TreeIndex, Vec<EntryDataBundle>)>,
+    /// The selected directory, and its entries.
+    pub preview: Option<(TreeIndex, Vec<EntryDataBundle>)>,
+}
+
+#[derive(PartialEq, Eq)]
+struct ColumnsKey {
+    root: TreeIndex,
+    selected: Option<TreeIndex>,
+    sorting: SortMode,
+    size_kind: SizeKind,
+    /// Changes if the traversal was made again.
+    start: Instant,
+    /// Changes while the traversal adds entries.
+    entries_traversed: u64,
+    /// Changes if entries were deleted.
+    node_count: usize,
+}
+
+impl Columns {
+    /// List the entries of the parent of `root` and of the `selected` directory again, unless neither of them nor
+    /// the way they are sorted nor the `traversal` changed.
+    pub fn refresh(
+        &mut self,
+        traversal: &Traversal,
+        root: TreeIndex,
+        selected: Option<TreeIndex>,
+        sorting: SortMode,
+        size_kind: SizeKind,
+    ) {
+        let key = ColumnsKey {
+            root,
+            selected,
+            sorting,
+            size_kind,
+            start: traversal.start,
+            entries_traversed: traversal.entries_traversed,
+            node_count: traversal.tree.node_count(),
+        };
+        if self.key.as_ref() == Some(&key) {
+            return;
+        }
+        let tree = &traversal.tree;
+        let with_entries = |index| (index, sorted_entries(tree, index, sorting, size_kind));
+        self.parent = tree
+            .neighbors_directed(root, Direction::Incoming)
+            .next()
+            .map(with_entries);
+        self.preview = selected
+            .filter(|selected| {
+                tree.neighbors_directed(*selected, Direction::Outgoing)
+                    .next()
+                    .is_some()
+            })
+            .map(with_entries);
+        self.key = Some(key);
+    }
+}
+
+/// Like [`sorted_entries()`], but with the entries of all `expanded` directories following them, recursively.
+pub fn tree_entries(
+    tree: &Tree,
+    node_idx: TreeIndex,
+    sorting: SortMode,
+    size_kind: SizeKind,
+    expanded: &BTreeSet<TreeIndex>,
+) -> Vec<EntryDataBundle> {
+    fn add_entries(
+        out: &mut Vec<EntryDataBundle>,
+        depth: usize,
+        tree: &Tree,
+        node_idx: TreeIndex,
+        sorting: SortMode,
+        size_kind: SizeKind,
+        expanded: &BTreeSet<TreeIndex>,
+    ) {
+        for mut entry in sorted_entries(tree, node_idx, sorting, size_kind) {
+            let index = entry.index;
+            entry.depth = depth;
+            out.push(entry);
+            if expanded.contains(&index) {
+                add_entries(out, depth + 1, tree, index, sorting, size_kind, expanded);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    add_entries(&mut out, 0, tree, node_idx, sorting, size_kind, expanded);
+    out
+}
+
 pub fn fit_string_graphemes_with_ellipsis(
     s: impl Into<String>,
     path_graphemes_count: usize,
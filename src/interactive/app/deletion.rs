@@ -0,0 +1,834 @@
+use crate::interactive::{
+    app::FocussedPane,
+    path_of,
+    widgets::{MainWindow, MarkMode, MarkPane},
+    AppState,
+};
+use crosstermion::input::{Event, Key};
+use dua::{
+    traverse::{SizeKind, Traversal, Tree, TreeIndex},
+    ByteFormat,
+};
+use jwalk::rayon::prelude::*;
+#[cfg(not(unix))]
+use jwalk::WalkDir;
+use petgraph::Direction;
+#[cfg(unix)]
+use rustix::{
+    fs::{AtFlags, Dir, FileType, Mode, OFlags, CWD},
+    io::Errno,
+    path::Arg,
+};
+use std::{
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    ffi::CString,
+    os::{fd::OwnedFd, unix::ffi::OsStrExt},
+};
+#[cfg(not(unix))]
+use std::{fs, io};
+
+/// The time to wait at least between waking the event loop to show the progress of a deletion.
+const WAKE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a deletion on a worker thread did so far, shared with the event loop which shows it.
+pub struct DeletionProgress {
+    files_removed: AtomicU64,
+    bytes_removed: AtomicU64,
+    current: Mutex<PathBuf>,
+    cancelled: AtomicBool,
+    last_wake: Mutex<Instant>,
+    wake_tx: Option<mpsc::Sender<Event>>,
+}
+
+impl DeletionProgress {
+    pub fn new(wake_tx: Option<mpsc::Sender<Event>>) -> Self {
+        DeletionProgress {
+            files_removed: Default::default(),
+            bytes_removed: Default::default(),
+            current: Default::default(),
+            cancelled: Default::default(),
+            last_wake: Mutex::new(Instant::now()),
+            wake_tx,
+        }
+    }
+
+    /// Record that an entry of `bytes` was removed at `path`, and show it if it wasn't shown for a while.
+    pub fn removed(&self, path: &Path, bytes: u64) {
+        self.files_removed.fetch_add(1, Ordering::Relaxed);
+        self.bytes_removed.fetch_add(bytes, Ordering::Relaxed);
+        // Entries are removed in parallel, and only one of them needs to be shown.
+        let mut last_wake = match self.last_wake.try_lock() {
+            Ok(last_wake) => last_wake,
+            Err(_) => return,
+        };
+        if last_wake.elapsed() >= WAKE_INTERVAL {
+            *last_wake = Instant::now();
+            drop(last_wake);
+            *self.current.lock().expect("no panic while locked") = path.to_owned();
+            self.wake();
+        }
+    }
+
+    /// The amount of files, links and directories removed so far.
+    pub fn files_removed(&self) -> u64 {
+        self.files_removed.load(Ordering::Relaxed)
+    }
+
+    /// The bytes of all entries removed so far, as known from the traversal.
+    pub fn bytes_removed(&self) -> u64 {
+        self.bytes_removed.load(Ordering::Relaxed)
+    }
+
+    /// Make the deletion stop as soon as possible.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Return `true` if the deletion should stop as soon as possible.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Let the event loop process an event that does nothing, just like a resize, to show the latest state.
+    fn wake(&self) {
+        if let Some(tx) = self.wake_tx.as_ref() {
+            tx.send(Event::Key(Key::Alt('\r'))).ok();
+        }
+    }
+}
+
+/// The result of deleting or trashing one of the marked entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Deleted,
+    /// The entry could not be deleted entirely, with the amount of errors that occurred.
+    Failed(usize),
+    /// The deletion stopped before the entry was deleted.
+    Cancelled,
+}
+
+/// A marked entry to delete or trash.
+pub struct MarkedEntry {
+    pub index: TreeIndex,
+    pub path: PathBuf,
+    /// The path that was scanned to find the entry, which `path` starts with.
+    pub scan_root: PathBuf,
+    pub sizes: Sizes,
+}
+
+/// The size of an entry and the ones of the entries below it by name, as known from the traversal.
+///
+/// It shows the progress of a deletion in bytes, which saves looking at each entry before it is removed.
+#[derive(Debug, Default)]
+pub struct Sizes {
+    /// The bytes of the entry itself, without the ones of the entries below it.
+    bytes: u64,
+    below: BTreeMap<OsString, Sizes>,
+}
+
+/// The sizes of entries that weren't seen by the traversal.
+static NO_SIZES: Sizes = Sizes {
+    bytes: 0,
+    below: BTreeMap::new(),
+};
+
+impl Sizes {
+    /// Collect the sizes of the given `kind` of the entry at `index` in `tree` and of all entries below it.
+    pub fn of(tree: &Tree, index: TreeIndex, kind: SizeKind) -> Self {
+        let below: BTreeMap<_, _> = tree
+            .neighbors_directed(index, Direction::Outgoing)
+            .map(|child| (tree[child].name.clone().into_os_string(), child))
+            .collect();
+        let bytes_below: u128 = below.values().map(|child| tree[*child].size(kind)).sum();
+        Sizes {
+            bytes: tree[index].size(kind).saturating_sub(bytes_below) as u64,
+            below: below
+                .into_iter()
+                .map(|(name, child)| (name, Sizes::of(tree, child, kind)))
+                .collect(),
+        }
+    }
+
+    /// Return the sizes of the entry `name` below this one.
+    fn get(&self, name: &OsStr) -> &Sizes {
+        self.below.get(name).unwrap_or(&NO_SIZES)
+    }
+
+    /// Return the sizes of the entry at `relative` path below this one.
+    #[cfg(not(unix))]
+    fn find(&self, relative: &Path) -> &Sizes {
+        relative
+            .components()
+            .fold(self, |sizes, component| sizes.get(component.as_os_str()))
+    }
+
+    /// Return the bytes of this entry and of all entries below it.
+    #[cfg(feature = "trash-move")]
+    fn total(&self) -> u64 {
+        self.bytes + self.below.values().map(Sizes::total).sum::<u64>()
+    }
+}
+
+fn delete_or_trash(
+    mode: MarkMode,
+    MarkedEntry {
+        path,
+        scan_root,
+        sizes,
+        ..
+    }: MarkedEntry,
+    progress: &DeletionProgress,
+) -> Result<(), usize> {
+    match mode {
+        MarkMode::Delete => delete_directory_recursively(path, &scan_root, &sizes, progress),
+        #[cfg(feature = "trash-move")]
+        MarkMode::Trash => match trash::delete(&path) {
+            Ok(()) => {
+                progress.removed(&path, sizes.total());
+                Ok(())
+            }
+            Err(_) => Err(1),
+        },
+    }
+}
+
+/// Marked entries that are deleted or trashed on a worker thread, one after another.
+pub struct DeletionJob {
+    pub mode: MarkMode,
+    pub num_entries: usize,
+    pub num_done: usize,
+    pub num_deleted: usize,
+    pub num_failed: usize,
+    format: ByteFormat,
+    progress: Arc<DeletionProgress>,
+    outcomes: mpsc::Receiver<(TreeIndex, Outcome)>,
+}
+
+impl DeletionJob {
+    /// Start deleting or trashing `entries` according to `mode` on a worker thread,
+    /// which uses `wake_tx` to let the event loop show its progress, and waits for `gate` to be unlocked if set.
+    pub fn spawn(
+        mode: MarkMode,
+        entries: Vec<MarkedEntry>,
+        format: ByteFormat,
+        wake_tx: Option<mpsc::Sender<Event>>,
+        gate: Option<Arc<Mutex<()>>>,
+    ) -> Self {
+        let progress = Arc::new(DeletionProgress::new(wake_tx));
+        let (outcomes_tx, outcomes) = mpsc::channel();
+        let num_entries = entries.len();
+        std::thread::spawn({
+            let progress = progress.clone();
+            move || {
+                if let Some(gate) = gate {
+                    drop(gate.lock());
+                }
+                for entry in entries {
+                    let index = entry.index;
+                    let outcome = if progress.is_cancelled() {
+                        Outcome::Cancelled
+                    } else {
+                        *progress.current.lock().expect("no panic while locked") =
+                            entry.path.clone();
+                        match delete_or_trash(mode, entry, &progress) {
+                            Ok(()) => Outcome::Deleted,
+                            // The entry is still there, even though some of its contents may be gone.
+                            Err(_) if progress.is_cancelled() => Outcome::Cancelled,
+                            Err(num_errors) => Outcome::Failed(num_errors),
+                        }
+                    };
+                    if outcomes_tx.send((index, outcome)).is_err() {
+                        return;
+                    }
+                    progress.wake();
+                }
+            }
+        });
+        DeletionJob {
+            mode,
+            num_entries,
+            num_done: 0,
+            num_deleted: 0,
+            num_failed: 0,
+            format,
+            progress,
+            outcomes,
+        }
+    }
+
+    /// Describe how far the deletion got.
+    pub fn status(&self) -> String {
+        format!(
+            "{} entry {} of {}, {} files with {} removed so far: {} - <Esc> cancels",
+            match self.mode {
+                MarkMode::Delete => "Deleting",
+                #[cfg(feature = "trash-move")]
+                MarkMode::Trash => "Trashing",
+            },
+            (self.num_done + 1).min(self.num_entries),
+            self.num_entries,
+            self.progress.files_removed(),
+            self.format
+                .display(self.progress.bytes_removed() as u128)
+                .to_string()
+                .trim(),
+            self.progress
+                .current
+                .lock()
+                .expect("no panic while locked")
+                .display()
+        )
+    }
+}
+
+impl AppState {
+    /// Start deleting or trashing all entries marked in `pane` according to `mode` in the background.
+    ///
+    /// Marks are removed as their entries are deleted, and remain if that failed.
+    pub fn start_deletion(
+        &mut self,
+        pane: &mut MarkPane,
+        mode: MarkMode,
+        traversal: &Traversal,
+        format: ByteFormat,
+    ) {
+        pane.start_deletion(traversal);
+        let entries = pane
+            .marked_sorted_by_index()
+            .into_iter()
+            .map(|(index, mark)| MarkedEntry {
+                index: *index,
+                path: mark.path.clone(),
+                scan_root: scan_root_of(traversal, *index),
+                sizes: Sizes::of(&traversal.tree, *index, self.size_kind),
+            })
+            .collect();
+        #[cfg(test)]
+        let gate = self.deletion_gate.clone();
+        #[cfg(not(test))]
+        let gate = None;
+        self.deletion = Some(DeletionJob::spawn(
+            mode,
+            entries,
+            format,
+            self.wake_tx.clone(),
+            gate,
+        ));
+        self.reset_message();
+    }
+
+    /// Ask the running deletion to stop, which keeps the marks of all entries it didn't delete.
+    pub fn cancel_deletion(&mut self) {
+        if let Some(job) = self.deletion.as_ref() {
+            job.progress.cancel();
+        }
+    }
+
+    /// Remove all entries from the tree that were deleted since the last call, and finish the deletion if it is done.
+    pub fn apply_deletion_outcomes(&mut self, window: &mut MainWindow, traversal: &mut Traversal) {
+        while let Some(res) = self.deletion.as_ref().map(|job| job.outcomes.try_recv()) {
+            match res {
+                Ok((index, outcome)) => self.apply_outcome(index, outcome, window, traversal),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => self.finish_deletion(),
+            }
+        }
+    }
+
+    /// Block until the running deletion is done, and apply all of its outcomes.
+    pub fn wait_for_deletion(&mut self, window: &mut MainWindow, traversal: &mut Traversal) {
+        while let Some(res) = self.deletion.as_ref().map(|job| job.outcomes.recv()) {
+            match res {
+                Ok((index, outcome)) => self.apply_outcome(index, outcome, window, traversal),
+                Err(mpsc::RecvError) => self.finish_deletion(),
+            }
+        }
+    }
+
+    fn apply_outcome(
+        &mut self,
+        index: TreeIndex,
+        outcome: Outcome,
+        window: &mut MainWindow,
+        traversal: &mut Traversal,
+    ) {
+        let job = self.deletion.as_mut().expect("a running deletion");
+        job.num_done += 1;
+        match outcome {
+            Outcome::Deleted => {
+                job.num_deleted += 1;
+                if traversal.tree.contains_node(index) {
+                    self.delete_entries_in_traversal(index, traversal);
+                }
+                // Entries marked below the deleted one while it was deleted are gone as well.
+                window.mark_pane = window
+                    .mark_pane
+                    .take()
+                    .and_then(|pane| pane.retain_existing(&traversal.tree));
+                if window.mark_pane.is_none() && self.focussed == FocussedPane::Mark {
+                    self.focussed = FocussedPane::Main;
+                }
+            }
+            Outcome::Failed(num_errors) => {
+                job.num_failed += 1;
+                if let Some(pane) = window.mark_pane.as_mut() {
+                    pane.set_errors(index, num_errors);
+                }
+            }
+            Outcome::Cancelled => {}
+        }
+    }
+
+    fn finish_deletion(&mut self) {
+        let job = match self.deletion.take() {
+            Some(job) => job,
+            None => return,
+        };
+        let verb = match job.mode {
+            MarkMode::Delete => "deleted",
+            #[cfg(feature = "trash-move")]
+            MarkMode::Trash => "trashed",
+        };
+        self.message = Some(if job.progress.is_cancelled() {
+            format!(
+                "Cancelled after {} of {} entries were {}",
+                job.num_deleted, job.num_entries, verb
+            )
+        } else if job.num_failed > 0 {
+            format!(
+                "{} of {} entries were {}, {} failed and remain marked",
+                job.num_deleted, job.num_entries, verb, job.num_failed
+            )
+        } else {
+            format!("All {} entries were {}", job.num_entries, verb)
+        });
+    }
+}
+
+/// Return the path that was scanned to find the entry at `index`.
+fn scan_root_of(traversal: &Traversal, mut index: TreeIndex) -> PathBuf {
+    while let Some(parent) = traversal
+        .tree
+        .neighbors_directed(index, Direction::Incoming)
+        .next()
+    {
+        if parent == traversal.root_index {
+            break;
+        }
+        index = parent;
+    }
+    path_of(&traversal.tree, index)
+}
+
+/// Delete `path` and everything below it without following symlinks, and report each removed entry to `progress`.
+///
+/// All entries are removed relative to handles of the directories containing them, which are opened without following
+/// symlinks below `scan_root`, the path that was scanned to find `path`. Thus, replacing a directory with a symlink
+/// after it was scanned or while it is deleted can't make entries elsewhere be deleted.
+/// Sibling directories are deleted in parallel across the rayon pool, each with a handle of its own, and so are the files
+/// in each directory before the directory itself is removed. Below [`MAX_PARALLEL_DEPTH`] nested directories deleted
+/// in parallel, directories are traversed depth-first with only the handle of the current one open instead.
+/// Entries that don't exist (anymore) count as removed, and each entry that can't be removed counts as one error.
+/// Stops early if `progress` was cancelled, and only returns an error then if `path` itself wasn't removed.
+/// The removed bytes are taken from `sizes`.
+#[cfg(unix)]
+pub fn delete_directory_recursively(
+    path: PathBuf,
+    scan_root: &Path,
+    sizes: &Sizes,
+    progress: &DeletionProgress,
+) -> Result<(), usize> {
+    let name = match path.file_name() {
+        Some(name) => name,
+        None => return Err(1),
+    };
+    let parent = match open_parent(&path, scan_root) {
+        Ok(parent) => parent,
+        Err(Errno::NOENT) => return Ok(()),
+        Err(_) => return Err(1),
+    };
+    match rustix::fs::unlinkat(&parent, name, AtFlags::empty()) {
+        // Directories can only be removed once they are empty.
+        Err(Errno::ISDIR) | Err(Errno::PERM) => {
+            remove_tree(&parent, name, &path, sizes, progress, 0)
+        }
+        res => {
+            if res.is_ok() {
+                progress.removed(&path, sizes.bytes);
+            }
+            into_result(into_error_count(res))
+        }
+    }
+}
+
+/// The amount of nested directories whose subdirectories are deleted in parallel, each of which keeps a directory handle
+/// open until they are done. It bounds the amount of open handles and the stack depth of each thread.
+#[cfg(unix)]
+const MAX_PARALLEL_DEPTH: usize = 16;
+
+/// A directory that is being deleted, which is removed from its parent once all of its subdirectories are gone.
+#[cfg(unix)]
+struct Frame<'a> {
+    /// The name of the directory in its parent.
+    name: CString,
+    path: PathBuf,
+    sizes: &'a Sizes,
+    /// The device and inode of the directory, to assure that `..` of its subdirectories still leads back to it.
+    id: (u64, u64),
+    /// The subdirectories that remain to be deleted.
+    subdirs: Vec<CString>,
+}
+
+/// Remove the directory `name` in `root` at `path` and everything in it, with `parallel_depth` directories above it
+/// whose subdirectories are deleted in parallel.
+#[cfg(unix)]
+fn remove_tree(
+    root: &OwnedFd,
+    name: &OsStr,
+    path: &Path,
+    sizes: &Sizes,
+    progress: &DeletionProgress,
+    parallel_depth: usize,
+) -> Result<(), usize> {
+    let mut num_errors = 0;
+    let (mut dir, id) = match open_dir(root, name) {
+        Ok(dir) => dir,
+        // It was replaced by a file or a symlink since, which is removed instead of being followed.
+        Err(Errno::NOTDIR) | Err(Errno::LOOP) => {
+            return into_result(unlink_at(root, name, path, sizes, progress))
+        }
+        Err(err) => return into_result(into_error_count(Err(err))),
+    };
+    let mut stack = vec![Frame {
+        name: CString::new(name.as_bytes()).map_err(|_| 1usize)?,
+        path: path.to_owned(),
+        sizes,
+        id,
+        subdirs: remove_files_of(&dir, path, sizes, progress, &mut num_errors),
+    }];
+    loop {
+        if progress.is_cancelled() {
+            return Err(num_errors);
+        }
+        let frame = stack
+            .last_mut()
+            .expect("the top directory remains until it is removed");
+        if frame.subdirs.len() > 1 && parallel_depth < MAX_PARALLEL_DEPTH {
+            let (parent_path, parent_sizes) = (&frame.path, frame.sizes);
+            num_errors += std::mem::take(&mut frame.subdirs)
+                .into_par_iter()
+                .map(|name| {
+                    let name = OsStr::from_bytes(name.to_bytes());
+                    let path = parent_path.join(name);
+                    let sizes = parent_sizes.get(name);
+                    remove_tree(&dir, name, &path, sizes, progress, parallel_depth + 1)
+                        .err()
+                        .unwrap_or_default()
+                })
+                .sum::<usize>();
+            continue;
+        }
+        if let Some(name) = frame.subdirs.pop() {
+            let path = frame.path.join(OsStr::from_bytes(name.to_bytes()));
+            let sizes = frame.sizes.get(OsStr::from_bytes(name.to_bytes()));
+            match open_dir(&dir, name.as_c_str()) {
+                Ok((subdir, id)) => {
+                    let subdirs = remove_files_of(&subdir, &path, sizes, progress, &mut num_errors);
+                    // The handle of the parent is closed, and opened again once its subdirectory is gone.
+                    dir = subdir;
+                    stack.push(Frame {
+                        name,
+                        path,
+                        sizes,
+                        id,
+                        subdirs,
+                    });
+                }
+                Err(Errno::NOTDIR) | Err(Errno::LOOP) => {
+                    num_errors += unlink_at(&dir, name.as_c_str(), &path, sizes, progress)
+                }
+                Err(err) => num_errors += into_error_count(Err(err)),
+            }
+            continue;
+        }
+
+        let frame = stack.pop().expect("the frame that was just looked at");
+        let parent = match stack.last() {
+            Some(parent) => match open_dir(&dir, "..") {
+                Ok((dir, id)) if id == parent.id => dir,
+                // The directory was moved while it was deleted, so what's above it now must not be touched.
+                _ => return Err(num_errors + 1),
+            },
+            None => {
+                drop(dir);
+                let res = rustix::fs::unlinkat(root, frame.name.as_c_str(), AtFlags::REMOVEDIR);
+                if res.is_ok() {
+                    progress.removed(&frame.path, frame.sizes.bytes);
+                }
+                return into_result(num_errors + into_error_count(res));
+            }
+        };
+        dir = parent;
+        let res = rustix::fs::unlinkat(&dir, frame.name.as_c_str(), AtFlags::REMOVEDIR);
+        if res.is_ok() {
+            progress.removed(&frame.path, frame.sizes.bytes);
+        }
+        num_errors += into_error_count(res);
+    }
+}
+
+/// Open the directory containing `path`, which is `scan_root` or below it.
+///
+/// Symlinks are only followed up to `scan_root` as that is the path that was given to be scanned, but not below it
+/// where the scan saw no symlinks to directories.
+#[cfg(unix)]
+fn open_parent(path: &Path, scan_root: &Path) -> Result<OwnedFd, Errno> {
+    let open = |dir: &Path| {
+        rustix::fs::openat(
+            CWD,
+            dir,
+            OFlags::RDONLY | OFlags::DIRECTORY | OFlags::CLOEXEC,
+            Mode::empty(),
+        )
+    };
+    let dirs_below_root = match path.strip_prefix(scan_root) {
+        Ok(relative) => match relative.parent() {
+            Some(dirs) => dirs,
+            // The scanned path itself is deleted.
+            None => {
+                return open(match path.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => parent,
+                    _ => Path::new("."),
+                })
+            }
+        },
+        Err(_) => return Err(Errno::INVAL),
+    };
+    let mut dir = open(scan_root)?;
+    for name in dirs_below_root {
+        dir = open_dir(&dir, name)?.0;
+    }
+    Ok(dir)
+}
+
+/// Open the directory `name` in `parent` without following symlinks, and return it along with its device and inode.
+#[cfg(unix)]
+fn open_dir(parent: &OwnedFd, name: impl Arg) -> Result<(OwnedFd, (u64, u64)), Errno> {
+    let dir = rustix::fs::openat(
+        parent,
+        name,
+        OFlags::RDONLY | OFlags::DIRECTORY | OFlags::NOFOLLOW | OFlags::CLOEXEC,
+        Mode::empty(),
+    )?;
+    let stat = rustix::fs::fstat(&dir)?;
+    Ok((dir, (stat.st_dev as u64, stat.st_ino as u64)))
+}
+
+/// Remove all entries but directories in `dir` at `path` with `sizes` across the rayon pool, count those that couldn't
+/// be removed in `num_errors`, and return the names of the directories.
+#[cfg(unix)]
+fn remove_files_of(
+    dir: &OwnedFd,
+    path: &Path,
+    sizes: &Sizes,
+    progress: &DeletionProgress,
+    num_errors: &mut usize,
+) -> Vec<CString> {
+    let mut subdirs = Vec::new();
+    let mut files = Vec::new();
+    match Dir::read_from(dir) {
+        Ok(entries) => {
+            for entry in entries {
+                match entry {
+                    Ok(entry) => {
+                        let name = entry.file_name();
+                        if name.to_bytes() == b"." || name.to_bytes() == b".." {
+                            continue;
+                        }
+                        if entry.file_type() == FileType::Directory {
+                            subdirs.push(name.to_owned());
+                        } else {
+                            files.push(name.to_owned());
+                        }
+                    }
+                    Err(err) => *num_errors += into_error_count(Err(err)),
+                }
+            }
+        }
+        Err(err) => *num_errors += into_error_count(Err(err)),
+    }
+    let unlinked: Vec<(Option<CString>, usize)> = files
+        .into_par_iter()
+        .map(|name| {
+            if progress.is_cancelled() {
+                return (None, 0);
+            }
+            match rustix::fs::unlinkat(dir, name.as_c_str(), AtFlags::empty()) {
+                // Its type wasn't known, or it was replaced by a directory since it was read.
+                Err(Errno::ISDIR) | Err(Errno::PERM) => (Some(name), 0),
+                res => {
+                    if res.is_ok() {
+                        progress.removed(path, sizes.get(OsStr::from_bytes(name.to_bytes())).bytes);
+                    }
+                    (None, into_error_count(res))
+                }
+            }
+        })
+        .collect();
+    for (subdir, errors) in unlinked {
+        subdirs.extend(subdir);
+        *num_errors += errors;
+    }
+    subdirs
+}
+
+/// Unlink the entry `name` in `parent` at `path` with `sizes` if it isn't a directory, and return the amount of errors.
+#[cfg(unix)]
+fn unlink_at(
+    parent: &OwnedFd,
+    name: impl Arg,
+    path: &Path,
+    sizes: &Sizes,
+    progress: &DeletionProgress,
+) -> usize {
+    let res = rustix::fs::unlinkat(parent, name, AtFlags::empty());
+    if res.is_ok() {
+        progress.removed(path, sizes.bytes);
+    }
+    into_error_count(res)
+}
+
+#[cfg(unix)]
+fn into_error_count(res: Result<(), Errno>) -> usize {
+    match res {
+        Ok(()) | Err(Errno::NOENT) => 0,
+        Err(_) => 1,
+    }
+}
+
+/// The amount of files to remove in parallel at once, so removing them starts while directories are still read.
+#[cfg(not(unix))]
+const REMOVAL_BATCH_SIZE: usize = 1024;
+
+/// Delete `path` and everything below it without following symlinks, and report each removed entry to `progress`.
+///
+/// Directories are read in parallel and the files in them are removed across the rayon pool, before the directories
+/// themselves are removed bottom-up. Entries that don't exist (anymore) count as removed, and each entry that can't be
+/// removed counts as one error. Stops early if `progress` was cancelled, and only returns an error then if `path` itself
+/// wasn't removed.
+/// Entries are removed by path here, so unlike on unix, symlinks leading to `path` below `_scan_root` are followed.
+#[cfg(not(unix))]
+pub fn delete_directory_recursively(
+    path: PathBuf,
+    _scan_root: &Path,
+    sizes: &Sizes,
+    progress: &DeletionProgress,
+) -> Result<(), usize> {
+    let is_dir = match path.symlink_metadata() {
+        // symlinks are no directories here, so they are never followed
+        Ok(metadata) => metadata.is_dir(),
+        Err(err) => return into_result(into_error_count(Err(err))),
+    };
+    if !is_dir {
+        return into_result(remove_file(&path, sizes.bytes, progress));
+    }
+
+    let mut num_errors = 0;
+    let mut dirs_by_depth: Vec<Vec<(PathBuf, u64)>> = Vec::new();
+    let mut files = Vec::with_capacity(REMOVAL_BATCH_SIZE);
+    let remove_files = |files: &mut Vec<(PathBuf, u64)>| -> usize {
+        files
+            .par_drain(..)
+            .map(|(file, bytes)| {
+                if progress.is_cancelled() {
+                    0
+                } else {
+                    remove_file(&file, bytes, progress)
+                }
+            })
+            .sum()
+    };
+    // Directories that can't be read can't be removed either, which is when their errors are counted.
+    for entry in WalkDir::new(&path)
+        .skip_hidden(false)
+        .follow_links(false)
+        .sort(false)
+        .into_iter()
+        .flatten()
+    {
+        if progress.is_cancelled() {
+            return Err(num_errors);
+        }
+        let entry_path = entry.path();
+        let bytes = entry_path
+            .strip_prefix(&path)
+            .map_or(0, |relative| sizes.find(relative).bytes);
+        if entry.file_type().is_dir() {
+            let depth = entry.depth();
+            if dirs_by_depth.len() <= depth {
+                dirs_by_depth.resize_with(depth + 1, Vec::new);
+            }
+            dirs_by_depth[depth].push((entry_path, bytes));
+        } else {
+            files.push((entry_path, bytes));
+            if files.len() == REMOVAL_BATCH_SIZE {
+                num_errors += remove_files(&mut files);
+            }
+        }
+    }
+    num_errors += remove_files(&mut files);
+
+    for dirs in dirs_by_depth.into_iter().rev() {
+        if progress.is_cancelled() {
+            return Err(num_errors);
+        }
+        num_errors += dirs
+            .par_iter()
+            .map(|(dir, bytes)| {
+                let res = fs::remove_dir(dir).or_else(|_| fs::remove_file(dir));
+                if res.is_ok() {
+                    progress.removed(dir, *bytes);
+                }
+                into_error_count(res)
+            })
+            .sum::<usize>();
+    }
+    into_result(num_errors)
+}
+
+/// Remove the file of `bytes` at `path`, and return the amount of errors.
+#[cfg(not(unix))]
+fn remove_file(path: &Path, bytes: u64, progress: &DeletionProgress) -> usize {
+    let res = fs::remove_file(path);
+    if res.is_ok() {
+        progress.removed(path, bytes);
+    }
+    into_error_count(res)
+}
+
+#[cfg(not(unix))]
+fn into_error_count(res: Result<(), io::Error>) -> usize {
+    match res {
+        Ok(()) => 0,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+        Err(_) => 1,
+    }
+}
+
+fn into_result(num_errors: usize) -> Result<(), usize> {
+    if num_errors == 0 {
+        Ok(())
+    } else {
+        Err(num_errors)
+    }
+}
@@ -1,25 +1,43 @@
 use crate::interactive::{
     sorted_entries,
-    widgets::{MainWindow, MainWindowProps},
-    ByteVisualization, CursorDirection, CursorMode, DisplayOptions, EntryDataBundle, MarkEntryMode,
-    SortMode,
+    widgets::{MainWindow, MainWindowProps, Theme},
+    Action, BookmarkRequest, ByteVisualization, Columns, CommandLine, CursorDirection, CursorMode,
+    DeletionJob, DeletionRequest, DisplayOptions, EntryDataBundle, GoTo, History, KeyBindings,
+    MarkEntryMode, MarksPreview, Scan, Scope, Search, SearchScope, SizeFilter, SortMode,
 };
 use anyhow::Result;
 use crosstermion::input::{input_channel, Event, Key};
 use dua::{
-    traverse::{Traversal, TreeIndex},
+    traverse::{SizeKind, Traversal, TreeIndex},
     WalkOptions, WalkResult,
 };
-use std::{collections::BTreeMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::mpsc::{self, Sender},
+};
 use tui::backend::Backend;
 use tui_react::Terminal;
 
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum FocussedPane {
     #[default]
     Main,
     Help,
     Mark,
+    Treemap,
+}
+
+impl FocussedPane {
+    /// The scope of key bindings that apply while this pane has focus.
+    pub fn scope(self) -> Scope {
+        match self {
+            FocussedPane::Main => Scope::Main,
+            FocussedPane::Help => Scope::Help,
+            FocussedPane::Mark => Scope::Mark,
+            FocussedPane::Treemap => Scope::Treemap,
+        }
+    }
 }
 
 #[derive(Default)]
@@ -27,16 +45,58 @@ pub struct AppState {
     pub root: TreeIndex,
     pub selected: Option<TreeIndex>,
     pub entries: Vec<EntryDataBundle>,
+    /// The entries shown next to `entries` if columns are shown.
+    pub columns: Columns,
     pub sorting: SortMode,
+    pub size_kind: SizeKind,
     pub message: Option<String>,
     pub focussed: FocussedPane,
     pub bookmarks: BTreeMap<TreeIndex, TreeIndex>,
     pub is_scanning: bool,
+    pub bindings: KeyBindings,
+    pub theme: Theme,
+    pub search: Option<Search>,
+    /// If set, entries are shown as a tree in which the `expanded` directories show their entries as well.
+    pub is_tree_view: bool,
+    pub expanded: BTreeSet<TreeIndex>,
+    /// Entries bookmarked under the name of a key, to jump back to them.
+    pub named_bookmarks: BTreeMap<char, TreeIndex>,
+    /// If set, the next key is the name of a bookmark to set or jump to.
+    pub bookmark_request: Option<BookmarkRequest>,
+    pub history: History,
+    /// If set, a path to go to is being edited.
+    pub go_to: Option<GoTo>,
+    /// If set, only entries of matching size are shown.
+    pub filter: Option<SizeFilter>,
+    pub command_line: CommandLine,
+    /// If set, the traversal can be made again.
+    pub scan: Option<Scan>,
+    /// If set, the traversal is made again once the event loop is left.
+    pub rescan_requested: bool,
+    /// If set, the entries between this one and the selected one are about to be marked.
+    pub visual_anchor: Option<TreeIndex>,
+    /// If set, files matching a rule are marked once the next key confirms it.
+    pub marks_preview: Option<MarksPreview>,
+    /// If set, marked entries are only deleted or trashed once confirmed.
+    pub confirm_deletion: bool,
+    /// If set, marked entries are deleted or trashed once the next key confirms it.
+    pub deletion_request: Option<DeletionRequest>,
+    /// If set, deleting or trashing marked entries writes a script doing so to this file instead.
+    pub dry_run: Option<PathBuf>,
+    /// If set, marked entries are being deleted or trashed in the background.
+    pub deletion: Option<DeletionJob>,
+    /// Sends events to the event loop, to wake it up from other threads.
+    pub wake_tx: Option<Sender<Event>>,
+    /// If set, deletions wait for it to be unlocked before they delete anything, so tests can control them.
+    #[cfg(test)]
+    pub deletion_gate: Option<std::sync::Arc<std::sync::Mutex<()>>>,
 }
 
 pub enum ProcessingResult {
     Finished(WalkResult),
     ExitRequested(WalkResult),
+    /// The traversal should be made again, before the remaining events are processed.
+    RescanRequested,
 }
 
 impl AppState {
@@ -50,6 +110,18 @@ impl AppState {
     where
         B: Backend,
     {
+        if let Some(pane) = window.mark_pane.as_mut() {
+            pane.refresh(traversal);
+        }
+        if display.show_columns {
+            self.columns.refresh(
+                traversal,
+                self.root,
+                self.selected,
+                self.sorting,
+                self.size_kind,
+            );
+        }
         let props = MainWindowProps {
             traversal,
             display,
@@ -69,28 +141,56 @@ impl AppState {
     where
         B: Backend,
     {
-        use crosstermion::input::Key::*;
         use FocussedPane::*;
 
         self.draw(window, traversal, *display, terminal)?;
         for event in events {
             let key = match event {
                 Event::Key(key) => key,
-                Event::Resize(_, _) => Alt('\r'),
+                Event::Resize(_, _) => Key::Alt('\r'),
             };
 
             self.reset_message();
-            match key {
-                Char('?') => self.toggle_help_pane(window),
-                Char('\t') => {
+            self.apply_deletion_outcomes(window, traversal);
+            if self.process_deletion_request_input(key, window, traversal, display.byte_format)
+                || self.process_marks_preview_input(key, window, traversal)
+                || self.process_bookmark_input(key, traversal)
+                || self.process_go_to_input(key, traversal)
+                || self.process_command_line_input(key, window, traversal, display.byte_format)
+                || self.process_search_input(key, traversal)
+            {
+                if self.rescan_requested {
+                    return Ok(ProcessingResult::RescanRequested);
+                }
+                self.draw(window, traversal, *display, terminal)?;
+                continue;
+            }
+            let action = match self
+                .bindings
+                .action(self.focussed.scope(), key)
+                .or_else(|| self.bindings.action(Scope::Global, key))
+            {
+                Some(action) => action,
+                None => {
+                    self.draw(window, traversal, *display, terminal)?;
+                    continue;
+                }
+            };
+            match action {
+                Action::ToggleHelp => self.toggle_help_pane(window),
+                Action::ToggleTreemap => self.toggle_treemap_pane(window),
+                Action::CycleFocus => {
                     self.cycle_focus(window);
                 }
-                Ctrl('c') => {
+                Action::Exit => {
                     return Ok(ProcessingResult::ExitRequested(WalkResult {
                         num_errors: traversal.io_errors,
                     }))
                 }
-                Char('q') | Esc => match self.focussed {
+                Action::Quit => match self.focussed {
+                    Main if self.visual_anchor.is_some() => self.visual_anchor = None,
+                    Main if self.search.is_some() => self.search = None,
+                    Main | Mark if self.deletion.is_some() => self.cancel_deletion(),
                     Main => {
                         return Ok(ProcessingResult::ExitRequested(WalkResult {
                             num_errors: traversal.io_errors,
@@ -101,55 +201,86 @@ impl AppState {
                         self.focussed = Main;
                         window.help_pane = None
                     }
-                },
-                _ => {}
-            }
-
-            match self.focussed {
-                Mark => self.dispatch_to_mark_pane(key, window, traversal, *display, terminal),
-                Help => {
-                    window
-                        .help_pane
-                        .as_mut()
-                        .expect("help pane")
-                        .process_events(key);
-                }
-                Main => match key {
-                    Char('O') => self.open_that(traversal),
-                    Char(' ') => self.mark_entry(
-                        CursorMode::KeepPosition,
-                        MarkEntryMode::Toggle,
-                        window,
-                        traversal,
-                    ),
-                    Char('d') => self.mark_entry(
-                        CursorMode::Advance,
-                        MarkEntryMode::Toggle,
-                        window,
-                        traversal,
-                    ),
-                    Char('x') => self.mark_entry(
-                        CursorMode::Advance,
-                        MarkEntryMode::MarkForDeletion,
-                        window,
-                        traversal,
-                    ),
-                    Char('a') => self.mark_all_entries(MarkEntryMode::Toggle, window, traversal),
-                    Char('u') | Char('h') | Backspace | Left => {
-                        self.exit_node_with_traversal(traversal)
+                    Treemap => {
+                        self.focussed = Main;
+                        window.treemap_pane = None
                     }
-                    Char('o') | Char('l') | Char('\n') | Right => {
-                        self.enter_node_with_traversal(traversal)
+                },
+                _ => match self.focussed {
+                    Mark => self.dispatch_to_mark_pane(action, window, traversal, *display),
+                    Help => {
+                        window
+                            .help_pane
+                            .as_mut()
+                            .expect("help pane")
+                            .process_events(action);
                     }
-                    Char('H') | Home => self.change_entry_selection(CursorDirection::ToTop),
-                    Char('G') | End => self.change_entry_selection(CursorDirection::ToBottom),
-                    Ctrl('u') | PageUp => self.change_entry_selection(CursorDirection::PageUp),
-                    Char('k') | Up => self.change_entry_selection(CursorDirection::Up),
-                    Char('j') | Down => self.change_entry_selection(CursorDirection::Down),
-                    Ctrl('d') | PageDown => self.change_entry_selection(CursorDirection::PageDown),
-                    Char('s') => self.cycle_sorting(traversal),
-                    Char('g') => display.byte_vis.cycle(),
-                    _ => {}
+                    Treemap => self.dispatch_to_treemap_pane(action, window, traversal),
+                    Main => match action {
+                        Action::ToggleMark
+                        | Action::ToggleMarkAndAdvance
+                        | Action::MarkAndAdvance
+                            if self.visual_anchor.is_some() =>
+                        {
+                            self.mark_visual_range(window, traversal)
+                        }
+                        Action::OpenEntry => self.open_that(traversal),
+                        Action::ToggleMark => self.mark_entry(
+                            CursorMode::KeepPosition,
+                            MarkEntryMode::Toggle,
+                            window,
+                            traversal,
+                        ),
+                        Action::ToggleMarkAndAdvance => self.mark_entry(
+                            CursorMode::Advance,
+                            MarkEntryMode::Toggle,
+                            window,
+                            traversal,
+                        ),
+                        Action::MarkAndAdvance => self.mark_entry(
+                            CursorMode::Advance,
+                            MarkEntryMode::MarkForDeletion,
+                            window,
+                            traversal,
+                        ),
+                        Action::ToggleAllMarks => {
+                            self.mark_all_entries(MarkEntryMode::Toggle, window, traversal)
+                        }
+                        Action::MarkAll => self.mark_or_unmark_all(window, traversal),
+                        Action::VisualRange => self.toggle_visual_range(window, traversal),
+                        Action::UnmarkDirectory => self.unmark_directory(window, traversal),
+                        Action::ExitNode => self.exit_node_with_traversal(traversal),
+                        Action::SetBookmark => self.request_bookmark(BookmarkRequest::Set),
+                        Action::JumpToBookmark => self.request_bookmark(BookmarkRequest::Jump),
+                        Action::HistoryBack => self.go_back(traversal),
+                        Action::HistoryForward => self.go_forward(traversal),
+                        Action::GoToPath => self.start_go_to(traversal),
+                        Action::CommandLine => self.start_command_line(),
+                        Action::EnterNode => self.enter_node_with_traversal(traversal),
+                        Action::ToTop => self.change_entry_selection(CursorDirection::ToTop),
+                        Action::ToBottom => self.change_entry_selection(CursorDirection::ToBottom),
+                        Action::PageUp => self.change_entry_selection(CursorDirection::PageUp),
+                        Action::Up => self.change_entry_selection(CursorDirection::Up),
+                        Action::Down => self.change_entry_selection(CursorDirection::Down),
+                        Action::PageDown => self.change_entry_selection(CursorDirection::PageDown),
+                        Action::Search => self.start_search(SearchScope::Directory),
+                        Action::SearchTree => self.start_search(SearchScope::Tree),
+                        Action::NextMatch => self.select_next_match(true, traversal),
+                        Action::PreviousMatch => self.select_next_match(false, traversal),
+                        Action::ToggleSortBySize => {
+                            self.change_sorting(SortMode::toggle_size, traversal)
+                        }
+                        Action::CycleSorting => self.change_sorting(SortMode::cycle, traversal),
+                        Action::ReverseSorting => self.change_sorting(SortMode::reverse, traversal),
+                        Action::ToggleSizeKind => self.toggle_size_kind(traversal),
+                        Action::CycleByteVisualization => display.byte_vis.cycle(),
+                        Action::ToggleTreeView => self.toggle_tree_view(traversal),
+                        Action::ToggleColumns => display.show_columns = !display.show_columns,
+                        Action::ToggleSizeRatio => {
+                            display.show_size_ratio = !display.show_size_ratio
+                        }
+                        _ => {}
+                    },
                 },
             };
             self.draw(window, traversal, *display, terminal)?;
@@ -174,6 +305,19 @@ where
     Ok(())
 }
 
+/// Options to configure the interactive disk usage analyser with at startup
+#[derive(Default, Clone)]
+pub struct AppOptions {
+    pub sorting: SortMode,
+    pub byte_vis: ByteVisualization,
+    pub bindings: KeyBindings,
+    pub theme: Theme,
+    /// If set, ask for confirmation before deleting or trashing marked entries.
+    pub confirm_deletion: bool,
+    /// If set, write a script deleting or trashing marked entries to this file instead of doing so.
+    pub dry_run: Option<PathBuf>,
+}
+
 /// State and methods representing the interactive disk usage analyser for the terminal
 pub struct TerminalApp {
     pub traversal: Traversal,
@@ -200,6 +344,13 @@ impl TerminalApp {
             )
             .ok();
     }
+    /// Block until marked entries that are deleted in the background are gone, and update the tree accordingly.
+    pub fn wait_for_deletion(&mut self) {
+        self.state
+            .wait_for_deletion(&mut self.window, &mut self.traversal);
+    }
+
+    #[cfg(test)]
     pub fn process_events<B>(
         &mut self,
         terminal: &mut Terminal<B>,
@@ -208,20 +359,120 @@ impl TerminalApp {
     where
         B: Backend,
     {
-        match self.state.process_events(
-            &mut self.window,
-            &mut self.traversal,
-            &mut self.display,
-            terminal,
-            events,
-        )? {
-            ProcessingResult::Finished(res) | ProcessingResult::ExitRequested(res) => Ok(res),
+        self.process_events_while_rescanning(terminal, events, Vec::new)
+    }
+
+    /// Process all events from `keys_rx` until the application should exit, which are also processed while rescanning.
+    pub fn run<B>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        keys_rx: std::sync::mpsc::Receiver<Event>,
+    ) -> Result<WalkResult>
+    where
+        B: Backend,
+    {
+        self.process_events_while_rescanning(terminal, keys_rx.iter(), || {
+            keys_rx.try_iter().collect()
+        })
+    }
+
+    /// Process `events`, and those returned by `fetch_buffered_events` while the traversal is made again.
+    fn process_events_while_rescanning<B>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        mut events: impl Iterator<Item = Event>,
+        mut fetch_buffered_events: impl FnMut() -> Vec<Event>,
+    ) -> Result<WalkResult>
+    where
+        B: Backend,
+    {
+        loop {
+            match self.state.process_events(
+                &mut self.window,
+                &mut self.traversal,
+                &mut self.display,
+                terminal,
+                events.by_ref(),
+            )? {
+                ProcessingResult::Finished(res) | ProcessingResult::ExitRequested(res) => {
+                    return Ok(res)
+                }
+                ProcessingResult::RescanRequested => {
+                    self.rescan(terminal, &mut fetch_buffered_events)?
+                }
+            }
         }
     }
 
+    /// Make the traversal again while showing its progress just like the initial one,
+    /// and process events from `fetch_buffered_events` meanwhile. Exiting aborts it, which keeps the current traversal.
+    fn rescan<B>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        mut fetch_buffered_events: impl FnMut() -> Vec<Event>,
+    ) -> Result<()>
+    where
+        B: Backend,
+    {
+        let TerminalApp {
+            state,
+            window,
+            display,
+            traversal,
+        } = self;
+        state.rescan_requested = false;
+        let scan = state
+            .scan
+            .clone()
+            .expect("rescans are only requested if there is a scan");
+        let (root_path, marked) = state.forget_traversal(window, traversal);
+        state.is_scanning = true;
+        let mut is_first_update = true;
+        let mut received_events = false;
+        let rescanned = Traversal::from_walk(scan.walk_options, scan.input_paths, |traversal| {
+            if std::mem::take(&mut is_first_update) {
+                state.root = traversal.root_index;
+            }
+            state.entries = state.entries_at(&traversal.tree, state.root);
+            if !received_events {
+                state.selected = state.entries.first().map(|b| b.index);
+            }
+            state.reset_message(); // force "scanning" to appear
+            let events = fetch_buffered_events();
+            received_events |= !events.is_empty();
+
+            let should_abort = match state.process_events(
+                window,
+                traversal,
+                display,
+                terminal,
+                events.into_iter(),
+            )? {
+                ProcessingResult::ExitRequested(_) => true,
+                ProcessingResult::Finished(_) | ProcessingResult::RescanRequested => false,
+            };
+            Ok(should_abort)
+        })?;
+        state.is_scanning = false;
+        let is_aborted = rescanned.is_none();
+        if let Some(rescanned) = rescanned {
+            *traversal = rescanned;
+        }
+        state.finish_rescan(
+            &root_path,
+            marked,
+            received_events,
+            is_aborted,
+            window,
+            traversal,
+        );
+        Ok(())
+    }
+
     pub fn initialize<B>(
         terminal: &mut Terminal<B>,
         options: WalkOptions,
+        app_options: AppOptions,
         input_paths: Vec<PathBuf>,
         mode: Interaction,
     ) -> Result<Option<KeyboardInputAndApp>>
@@ -231,15 +482,22 @@ impl TerminalApp {
         terminal.hide_cursor()?;
         terminal.clear()?;
         let mut display: DisplayOptions = options.clone().into();
-        display.byte_vis = ByteVisualization::PercentageAndBar;
+        display.byte_vis = app_options.byte_vis;
+        let size_kind = SizeKind::from(options.apparent_size);
         let mut window = MainWindow::default();
-        let keys_rx = match mode {
-            Interaction::None => {
-                let (_, keys_rx) = std::sync::mpsc::channel();
-                keys_rx
-            }
-            Interaction::Full => input_channel(),
-        };
+        // Input is forwarded to a channel that other threads can send events to as well, to wake the event loop.
+        let (wake_tx, keys_rx) = mpsc::channel();
+        if let Interaction::Full = mode {
+            let input = input_channel();
+            let tx = wake_tx.clone();
+            std::thread::spawn(move || {
+                for event in input {
+                    if tx.send(event).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
 
         let fetch_buffered_key_events = || {
             let mut keys = Vec::new();
@@ -249,28 +507,42 @@ impl TerminalApp {
             keys
         };
 
+        let scan = Scan {
+            walk_options: options.clone(),
+            input_paths: input_paths.clone(),
+        };
         let mut state = None::<AppState>;
         let mut received_events = false;
         let traversal = Traversal::from_walk(options, input_paths, |traversal| {
             let s = match state.as_mut() {
                 Some(s) => {
-                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting);
+                    s.entries = s.entries_at(&traversal.tree, s.root);
                     if !received_events {
-                        s.selected = s.entries.get(0).map(|b| b.index);
+                        s.selected = s.entries.first().map(|b| b.index);
                     }
                     s
                 }
                 None => {
                     state = Some({
-                        let sorting = Default::default();
-                        let entries =
-                            sorted_entries(&traversal.tree, traversal.root_index, sorting);
+                        let sorting = app_options.sorting;
+                        let entries = sorted_entries(
+                            &traversal.tree,
+                            traversal.root_index,
+                            sorting,
+                            size_kind,
+                        );
                         AppState {
                             root: traversal.root_index,
                             sorting,
-                            selected: entries.get(0).map(|b| b.index),
+                            size_kind,
+                            selected: entries.first().map(|b| b.index),
                             entries,
                             is_scanning: true,
+                            bindings: app_options.bindings.clone(),
+                            theme: app_options.theme.clone(),
+                            confirm_deletion: app_options.confirm_deletion,
+                            dry_run: app_options.dry_run.clone(),
+                            wake_tx: Some(wake_tx.clone()),
                             ..Default::default()
                         }
                     });
@@ -289,7 +561,7 @@ impl TerminalApp {
                 events.into_iter(),
             )? {
                 ProcessingResult::ExitRequested(_) => true,
-                ProcessingResult::Finished(_) => false,
+                ProcessingResult::Finished(_) | ProcessingResult::RescanRequested => false,
             };
             Ok(should_exit)
         })?;
@@ -302,22 +574,29 @@ impl TerminalApp {
             let mut app = TerminalApp {
                 state: {
                     let mut s = state.unwrap_or_else(|| {
-                        let sorting = Default::default();
+                        let sorting = app_options.sorting;
                         let root = traversal.root_index;
-                        let entries = sorted_entries(&traversal.tree, root, sorting);
+                        let entries = sorted_entries(&traversal.tree, root, sorting, size_kind);
                         AppState {
                             root,
                             entries,
                             sorting,
+                            size_kind,
+                            bindings: app_options.bindings.clone(),
+                            theme: app_options.theme.clone(),
+                            confirm_deletion: app_options.confirm_deletion,
+                            dry_run: app_options.dry_run.clone(),
+                            wake_tx: Some(wake_tx.clone()),
                             ..Default::default()
                         }
                     });
                     s.is_scanning = false;
-                    s.entries = sorted_entries(&traversal.tree, s.root, s.sorting);
+                    s.scan = Some(scan);
+                    s.entries = s.entries_at(&traversal.tree, s.root);
                     s.selected = if received_events {
-                        s.selected.or_else(|| s.entries.get(0).map(|b| b.index))
+                        s.selected.or_else(|| s.entries.first().map(|b| b.index))
                     } else {
-                        s.entries.get(0).map(|b| b.index)
+                        s.entries.first().map(|b| b.index)
                     };
                     s
                 },
@@ -0,0 +1,209 @@
+use crate::interactive::{path_of, AppState};
+use crosstermion::input::Key;
+use dua::traverse::{Traversal, Tree, TreeIndex};
+use itertools::Itertools;
+use petgraph::Direction;
+use std::path::{Component, Path, PathBuf};
+
+/// A path to go to which is being edited, relative to the scanned paths or absolute.
+#[derive(Default, Debug, Clone)]
+pub struct GoTo {
+    pub text: String,
+    /// Information about the last completion or why the path can't be gone to.
+    pub status: Option<String>,
+    pub is_error: bool,
+}
+
+impl AppState {
+    /// Start editing a path to go to, starting with the one of the current directory.
+    pub fn start_go_to(&mut self, traversal: &Traversal) {
+        let mut text = String::new();
+        if self.root != traversal.root_index {
+            text = path_of(&traversal.tree, self.root).display().to_string();
+            text.push('/');
+        }
+        self.go_to = Some(GoTo {
+            text,
+            ..Default::default()
+        });
+    }
+
+    /// Edit the path to go to with `key` if it is being edited, and return `true` if the key was used for that.
+    pub fn process_go_to_input(&mut self, key: Key, traversal: &Traversal) -> bool {
+        let go_to = match self.go_to.as_mut() {
+            Some(go_to) => go_to,
+            None => return false,
+        };
+        go_to.status = None;
+        go_to.is_error = false;
+        match key {
+            Key::Esc => self.go_to = None,
+            Key::Char('\n') => match resolve_path(traversal, Path::new(&go_to.text)) {
+                Ok(index) => {
+                    self.go_to = None;
+                    self.go_to_index(index, traversal);
+                }
+                Err(err) => {
+                    go_to.status = Some(err);
+                    go_to.is_error = true;
+                }
+            },
+            Key::Char('\t') => complete(go_to, traversal),
+            Key::Backspace => {
+                go_to.text.pop();
+            }
+            Key::Ctrl('u') => go_to.text.clear(),
+            Key::Char(c) => go_to.text.push(c),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Show the entries of `index` if it is a directory, or the directory containing it otherwise, and select it.
+    pub fn go_to_index(&mut self, index: TreeIndex, traversal: &Traversal) {
+        let is_dir = index == traversal.root_index || traversal.tree[index].is_dir;
+        if is_dir {
+            if index != self.root {
+                self.history.leave(self.root);
+            }
+            self.visit(index, traversal);
+        } else {
+            self.select_in_tree(index, traversal);
+        }
+    }
+}
+
+/// Find the entry at `path`, which is relative to the scanned paths, or absolute if it lies within one of them.
+pub fn resolve_path(traversal: &Traversal, path: &Path) -> Result<TreeIndex, String> {
+    let tree = &traversal.tree;
+    if path.as_os_str().is_empty() {
+        return Ok(traversal.root_index);
+    }
+    let top_level: Vec<_> = children(tree, traversal.root_index).collect();
+    let mut not_found = None;
+    for top in &top_level {
+        let name = &tree[*top].name;
+        let rest = if path.is_absolute() {
+            absolute(name).and_then(|name| path.strip_prefix(name).ok().map(ToOwned::to_owned))
+        } else {
+            path.strip_prefix(name).ok().map(ToOwned::to_owned)
+        };
+        if let Some(rest) = rest {
+            match walk(tree, *top, &rest) {
+                Ok(index) => return Ok(index),
+                Err(err) => not_found = Some(err),
+            }
+        }
+    }
+    match (not_found, top_level.as_slice()) {
+        (Some(err), _) => Err(err),
+        (None, [single]) if path.is_relative() => walk(tree, *single, path),
+        (None, _) => Err(format!(
+            "'{}' is not within the scanned paths",
+            path.display()
+        )),
+    }
+}
+
+/// Follow the components of `path` from `index` down the tree.
+fn walk(tree: &Tree, mut index: TreeIndex, path: &Path) -> Result<TreeIndex, String> {
+    for component in path.components() {
+        index = match component {
+            Component::CurDir => index,
+            Component::ParentDir => {
+                parent(tree, index).ok_or("Can't go above the scanned paths")?
+            }
+            Component::Normal(name) => children(tree, index)
+                .find(|child| tree[*child].name.as_os_str() == name)
+                .ok_or_else(|| format!("There is no entry '{}'", name.to_string_lossy()))?,
+            Component::RootDir | Component::Prefix(_) => return Err("Unexpected root".into()),
+        };
+    }
+    Ok(index)
+}
+
+/// Complete the last component of the path in `go_to` with the names of the entries it could refer to,
+/// as far as they have a common prefix, and show them all if there is more than one.
+fn complete(go_to: &mut GoTo, traversal: &Traversal) {
+    let (dir, prefix) = match go_to.text.rfind('/') {
+        Some(pos) => go_to.text.split_at(pos + 1),
+        None => ("", go_to.text.as_str()),
+    };
+    let tree = &traversal.tree;
+    let dir_index = match resolve_path(traversal, Path::new(dir)) {
+        Ok(index) => index,
+        Err(err) => {
+            go_to.status = Some(err);
+            go_to.is_error = true;
+            return;
+        }
+    };
+    // Paths are relative to a single scanned path, so its entries are completed unless its name is typed.
+    let single_input = match children(tree, traversal.root_index).collect::<Vec<_>>()[..] {
+        [single] => Some(single),
+        _ => None,
+    };
+    let dir_index = match single_input {
+        Some(single) if dir.is_empty() && !prefix_of_name(tree, single, prefix) => single,
+        _ => dir_index,
+    };
+    let candidates: Vec<_> = children(tree, dir_index)
+        .filter(|idx| prefix_of_name(tree, *idx, prefix))
+        .map(|idx| {
+            let mut name = tree[idx].name.to_string_lossy().into_owned();
+            if tree[idx].is_dir {
+                name.push('/');
+            }
+            name
+        })
+        .sorted()
+        .collect();
+    let completed = match candidates.as_slice() {
+        [] => {
+            go_to.status = Some("No completions".into());
+            go_to.is_error = true;
+            return;
+        }
+        [single] => single.clone(),
+        [first, rest @ ..] => {
+            go_to.status = Some(candidates.join("  "));
+            rest.iter()
+                .fold(first.as_str(), |common, candidate| {
+                    let len = common
+                        .chars()
+                        .zip(candidate.chars())
+                        .take_while(|(l, r)| l == r)
+                        .map(|(c, _)| c.len_utf8())
+                        .sum();
+                    &common[..len]
+                })
+                .to_owned()
+        }
+    };
+    go_to.text = format!("{}{}", dir, completed);
+}
+
+fn prefix_of_name(tree: &Tree, index: TreeIndex, prefix: &str) -> bool {
+    tree[index].name.to_string_lossy().starts_with(prefix)
+}
+
+fn absolute(path: &Path) -> Option<PathBuf> {
+    if path.is_absolute() {
+        Some(path.to_owned())
+    } else {
+        std::env::current_dir().ok().map(|cwd| {
+            cwd.join(path)
+                .components()
+                .filter(|c| !matches!(c, Component::CurDir))
+                .collect()
+        })
+    }
+}
+
+fn children(tree: &Tree, index: TreeIndex) -> impl Iterator<Item = TreeIndex> + '_ {
+    tree.neighbors_directed(index, Direction::Outgoing)
+}
+
+fn parent(tree: &Tree, index: TreeIndex) -> Option<TreeIndex> {
+    tree.neighbors_directed(index, Direction::Incoming).next()
+}
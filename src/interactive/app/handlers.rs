@@ -1,16 +1,24 @@
 use crate::interactive::{
-    app::FocussedPane::*,
-    path_of, sorted_entries,
-    widgets::{HelpPane, MainWindow, MarkMode, MarkPane},
-    AppState, DisplayOptions, EntryDataBundle,
+    app::{FocussedPane, FocussedPane::*},
+    path_of, sorted_entries, tree_entries,
+    widgets::{DeletionSummary, HelpPane, MainWindow, MarkMode, MarkPane, Treemap},
+    Action, AppState, DisplayOptions, EntryDataBundle, SortMode,
 };
 use crosstermion::input::Key;
-use dua::traverse::{Traversal, TreeIndex};
+use dua::{
+    traverse::{Traversal, Tree, TreeIndex},
+    ByteFormat,
+};
 use itertools::Itertools;
 use petgraph::{visit::Bfs, Direction};
-use std::{fs, io, path::PathBuf};
-use tui::backend::Backend;
-use tui_react::Terminal;
+use std::ops::RangeInclusive;
+
+/// Marked entries that are about to be deleted or trashed once the next key confirms it.
+#[derive(Debug, Clone)]
+pub struct DeletionRequest {
+    pub mode: MarkMode,
+    pub summary: DeletionSummary,
+}
 
 #[derive(Copy, Clone)]
 pub enum CursorMode {
@@ -55,10 +63,36 @@ impl AppState {
     }
 
     pub fn exit_node_with_traversal(&mut self, traversal: &Traversal) {
+        if self.is_tree_view && self.collapse_or_select_parent(traversal) {
+            return;
+        }
         let entries = self.entries_for_exit_node(traversal);
         self.exit_node(entries);
     }
 
+    /// Collapse the selected directory if it is expanded, or select its parent if it is nested in the tree view.
+    /// Return `false` if neither was possible.
+    fn collapse_or_select_parent(&mut self, traversal: &Traversal) -> bool {
+        let (index, depth) = match self
+            .selected
+            .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
+        {
+            Some(entry) => (entry.index, entry.depth),
+            None => return false,
+        };
+        if self.expanded.remove(&index) {
+            self.entries = self.entries_at(&traversal.tree, self.root);
+        } else if depth > 0 {
+            self.selected = traversal
+                .tree
+                .neighbors_directed(index, Direction::Incoming)
+                .next();
+        } else {
+            return false;
+        }
+        true
+    }
+
     fn entries_for_exit_node(
         &self,
         traversal: &Traversal,
@@ -67,24 +101,20 @@ impl AppState {
             .tree
             .neighbors_directed(self.root, Direction::Incoming)
             .next()
-            .map(|parent_idx| {
-                (
-                    parent_idx,
-                    sorted_entries(&traversal.tree, parent_idx, self.sorting),
-                )
-            })
+            .map(|parent_idx| (parent_idx, self.entries_at(&traversal.tree, parent_idx)))
     }
 
     pub fn exit_node(&mut self, entries: Option<(TreeIndex, Vec<EntryDataBundle>)>) {
         match entries {
             Some((parent_idx, entries)) => {
+                self.history.leave(self.root);
                 self.root = parent_idx;
                 self.entries = entries;
                 self.selected = self
                     .bookmarks
                     .get(&parent_idx)
                     .copied()
-                    .or_else(|| self.entries.get(0).map(|b| b.index));
+                    .or_else(|| self.entries.first().map(|b| b.index));
             }
             None => self.message = Some("Top level reached".into()),
         }
@@ -97,12 +127,16 @@ impl AppState {
         self.selected.map(|previously_selected| {
             (
                 previously_selected,
-                sorted_entries(&traversal.tree, previously_selected, self.sorting),
+                self.entries_at(&traversal.tree, previously_selected),
             )
         })
     }
 
     pub fn enter_node_with_traversal(&mut self, traversal: &Traversal) {
+        if self.is_tree_view {
+            self.expand_or_select_child(traversal);
+            return;
+        }
         let new_entries = self.entries_for_enter_node(traversal);
         self.enter_node(new_entries)
     }
@@ -122,6 +156,7 @@ impl AppState {
             ) {
                 Some(b) => {
                     self.bookmarks.insert(self.root, previously_selected);
+                    self.history.leave(self.root);
                     self.root = previously_selected;
                     self.selected = Some(b.index);
                     self.entries = new_entries;
@@ -131,6 +166,77 @@ impl AppState {
         }
     }
 
+    /// Expand the selected directory, or select its first entry if it is expanded already.
+    fn expand_or_select_child(&mut self, traversal: &Traversal) {
+        let selected = match self.selected {
+            Some(selected) => selected,
+            None => return,
+        };
+        if self.expanded.contains(&selected) {
+            let entries = &self.entries;
+            if let Some(child) = entries
+                .iter()
+                .position(|e| e.index == selected)
+                .and_then(|pos| {
+                    entries
+                        .get(pos + 1)
+                        .filter(|c| c.depth > entries[pos].depth)
+                })
+            {
+                self.selected = Some(child.index);
+            }
+        } else if traversal
+            .tree
+            .neighbors_directed(selected, Direction::Outgoing)
+            .next()
+            .is_some()
+        {
+            self.expanded.insert(selected);
+            self.entries = self.entries_at(&traversal.tree, self.root);
+        } else {
+            self.message = Some("Entry is a file or an empty directory".into());
+        }
+    }
+
+    pub fn toggle_tree_view(&mut self, traversal: &Traversal) {
+        self.is_tree_view = !self.is_tree_view;
+        self.entries = self.entries_at(&traversal.tree, self.root);
+        if let Some(selected) = self.selected {
+            if !self.entries.iter().any(|e| e.index == selected) {
+                // the selection was nested in a collapsed directory, select its top-level ancestor instead
+                let parent_of = |idx| {
+                    traversal
+                        .tree
+                        .neighbors_directed(idx, Direction::Incoming)
+                        .next()
+                };
+                let mut ancestor = selected;
+                while let Some(parent) = parent_of(ancestor).filter(|p| *p != self.root) {
+                    ancestor = parent;
+                }
+                self.selected = self
+                    .entries
+                    .iter()
+                    .find(|e| e.index == ancestor)
+                    .or_else(|| self.entries.first())
+                    .map(|e| e.index);
+            }
+        }
+    }
+
+    /// The entries of `root` as they are shown, either as list or as tree.
+    pub fn entries_at(&self, tree: &Tree, root: TreeIndex) -> Vec<EntryDataBundle> {
+        let mut entries = if self.is_tree_view {
+            tree_entries(tree, root, self.sorting, self.size_kind, &self.expanded)
+        } else {
+            sorted_entries(tree, root, self.sorting, self.size_kind)
+        };
+        if let Some(filter) = self.filter {
+            entries.retain(|e| filter.matches(e.data.size(self.size_kind)));
+        }
+        entries
+    }
+
     pub fn change_entry_selection(&mut self, direction: CursorDirection) {
         let entries = &self.entries;
         let next_selected_pos = match self.selected {
@@ -151,13 +257,20 @@ impl AppState {
         }
     }
 
-    pub fn cycle_sorting(&mut self, traversal: &Traversal) {
-        self.sorting.toggle_size();
-        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting);
+    pub fn change_sorting(&mut self, change: fn(&mut SortMode), traversal: &Traversal) {
+        change(&mut self.sorting);
+        self.entries = self.entries_at(&traversal.tree, self.root);
+    }
+
+    pub fn toggle_size_kind(&mut self, traversal: &Traversal) {
+        self.size_kind.toggle();
+        self.entries = self.entries_at(&traversal.tree, self.root);
     }
 
     pub fn reset_message(&mut self) {
-        if self.is_scanning {
+        if let Some(job) = self.deletion.as_ref() {
+            self.message = Some(job.status());
+        } else if self.is_scanning {
             self.message = Some("-> scanning <-".into());
         } else {
             self.message = None;
@@ -166,92 +279,139 @@ impl AppState {
 
     pub fn toggle_help_pane(&mut self, window: &mut MainWindow) {
         self.focussed = match self.focussed {
-            Main | Mark => {
-                window.help_pane = Some(HelpPane::default());
-                Help
-            }
             Help => {
                 window.help_pane = None;
                 Main
             }
+            _ => {
+                window.help_pane = Some(HelpPane::default());
+                Help
+            }
         }
     }
+
+    pub fn toggle_treemap_pane(&mut self, window: &mut MainWindow) {
+        self.focussed = match window.treemap_pane {
+            Some(_) => {
+                window.treemap_pane = None;
+                if self.focussed == FocussedPane::Treemap {
+                    Main
+                } else {
+                    self.focussed
+                }
+            }
+            None => {
+                window.treemap_pane = Some(Treemap::default());
+                if let Some(pane) = window.mark_pane.as_mut() {
+                    pane.set_focus(false);
+                }
+                FocussedPane::Treemap
+            }
+        };
+    }
+
     pub fn cycle_focus(&mut self, window: &mut MainWindow) {
-        if let Some(p) = window.mark_pane.as_mut() {
-            p.set_focus(false)
+        let order = [Main, FocussedPane::Treemap, Help, Mark];
+        let is_open = |pane: &FocussedPane| match pane {
+            Main => true,
+            FocussedPane::Treemap => window.treemap_pane.is_some(),
+            Help => window.help_pane.is_some(),
+            Mark => window.mark_pane.is_some(),
         };
-        self.focussed = match (self.focussed, &window.help_pane, &mut window.mark_pane) {
-            (Main, Some(_), _) => Help,
-            (Help, _, Some(ref mut pane)) => {
-                pane.set_focus(true);
-                Mark
+        let current = order
+            .iter()
+            .position(|pane| *pane == self.focussed)
+            .unwrap_or(0);
+        self.focussed = order
+            .iter()
+            .cycle()
+            .skip(current + 1)
+            .take(order.len())
+            .find(|pane| is_open(pane))
+            .copied()
+            .unwrap_or(Main);
+        if let Some(pane) = window.mark_pane.as_mut() {
+            pane.set_focus(self.focussed == Mark);
+        }
+    }
+
+    pub fn dispatch_to_treemap_pane(
+        &mut self,
+        action: Action,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        match action {
+            Action::EnterNode => {
+                let entries = self.entries_for_enter_node(traversal);
+                self.enter_node(entries);
             }
-            (Help, _, None) => Main,
-            (Mark, _, _) => Main,
-            (Main, None, None) => Main,
-            (Main, None, Some(ref mut pane)) => {
-                pane.set_focus(true);
-                Mark
+            Action::ExitNode => {
+                let entries = self.entries_for_exit_node(traversal);
+                self.exit_node(entries);
             }
-        };
+            Action::SelectLeft
+            | Action::SelectRight
+            | Action::SelectAbove
+            | Action::SelectBelow => {
+                if let Some(selected) = window
+                    .treemap_pane
+                    .as_ref()
+                    .and_then(|pane| pane.neighbour(self.selected, action))
+                {
+                    self.selected = Some(selected);
+                }
+            }
+            _ => {}
+        }
     }
 
-    pub fn dispatch_to_mark_pane<B>(
+    pub fn dispatch_to_mark_pane(
         &mut self,
-        key: Key,
+        action: Action,
         window: &mut MainWindow,
-        traversal: &mut Traversal,
+        traversal: &Traversal,
         display: DisplayOptions,
-        terminal: &mut Terminal<B>,
-    ) where
-        B: Backend,
-    {
-        let res = window.mark_pane.take().and_then(|p| p.process_events(key));
+    ) {
+        if let Some(pane) = window.mark_pane.as_mut() {
+            pane.refresh(traversal);
+        }
+        if action == Action::WriteScript {
+            if let Some(pane) = window.mark_pane.as_ref() {
+                self.write_deletion_script(pane, pane.mode(), display.byte_format);
+            }
+            return;
+        }
+        let res = window
+            .mark_pane
+            .take()
+            .and_then(|p| p.process_events(action));
         window.mark_pane = match res {
-            Some((pane, mode)) => match mode {
-                Some(MarkMode::Delete) => {
-                    self.message = Some("Deleting entries...".to_string());
-                    let mut entries_deleted = 0;
-                    let res = pane.iterate_deletable_items(|mut pane, entry_to_delete| {
-                        window.mark_pane = Some(pane);
-                        self.draw(window, traversal, display, terminal).ok();
-                        pane = window.mark_pane.take().expect("option to be filled");
-                        match self.delete_entry(entry_to_delete, traversal) {
-                            Ok(ed) => {
-                                entries_deleted += ed;
-                                self.message =
-                                    Some(format!("Deleted {} entries...", entries_deleted));
-                                Ok(pane)
-                            }
-                            Err(c) => Err((pane, c)),
-                        }
-                    });
-                    self.message = None;
-                    res
-                }
-                #[cfg(feature = "trash-move")]
-                Some(MarkMode::Trash) => {
-                    self.message = Some("Trashing entries...".to_string());
-                    let mut entries_trashed = 0;
-                    let res = pane.iterate_deletable_items(|mut pane, entry_to_trash| {
-                        window.mark_pane = Some(pane);
-                        self.draw(window, traversal, display, terminal).ok();
-                        pane = window.mark_pane.take().expect("option to be filled");
-                        match self.trash_entry(entry_to_trash, traversal) {
-                            Ok(ed) => {
-                                entries_trashed += ed;
-                                self.message =
-                                    Some(format!("Trashed {} entries...", entries_trashed));
-                                Ok(pane)
-                            }
-                            Err(c) => Err((pane, c)),
-                        }
-                    });
-                    self.message = None;
-                    res
-                }
-                None => Some(pane),
-            },
+            Some((pane, Some(mode))) if self.dry_run.is_some() => {
+                self.write_deletion_script(&pane, mode, display.byte_format);
+                Some(pane)
+            }
+            Some((pane, Some(_))) if self.deletion.is_some() => {
+                self.message = Some("Marked entries are being deleted already".into());
+                Some(pane)
+            }
+            // The walk may still enter them, and marks made while rescanning are found again by path afterwards.
+            Some((pane, Some(_))) if self.is_scanning => {
+                self.message = Some("Marked entries can be deleted once scanning is done".into());
+                Some(pane)
+            }
+            Some((pane, Some(mode))) if self.confirm_deletion => {
+                self.deletion_request = Some(DeletionRequest {
+                    mode,
+                    summary: pane.deletion_summary(traversal, self.size_kind),
+                });
+                Some(pane)
+            }
+            Some((mut pane, Some(mode))) => {
+                self.start_deletion(&mut pane, mode, traversal, display.byte_format);
+                Some(pane)
+            }
+            Some((pane, None)) => Some(pane),
             None => None,
         };
         if window.mark_pane.is_none() {
@@ -259,35 +419,25 @@ impl AppState {
         }
     }
 
-    pub fn delete_entry(
-        &mut self,
-        index: TreeIndex,
-        traversal: &mut Traversal,
-    ) -> Result<usize, usize> {
-        let mut entries_deleted = 0;
-        if let Some(_entry) = traversal.tree.node_weight(index) {
-            let path_to_delete = path_of(&traversal.tree, index);
-            delete_directory_recursively(path_to_delete)?;
-            entries_deleted = self.delete_entries_in_traversal(index, traversal);
-        }
-        Ok(entries_deleted)
-    }
-
-    #[cfg(feature = "trash-move")]
-    pub fn trash_entry(
+    /// Start deleting the marked entries if `key` confirms the deletion request, and return `true` if there was one.
+    pub fn process_deletion_request_input(
         &mut self,
-        index: TreeIndex,
-        traversal: &mut Traversal,
-    ) -> Result<usize, usize> {
-        let mut entries_deleted = 0;
-        if let Some(_entry) = traversal.tree.node_weight(index) {
-            let path_to_delete = path_of(&traversal.tree, index);
-            if trash::delete(path_to_delete).is_err() {
-                return Err(1);
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+        format: ByteFormat,
+    ) -> bool {
+        let request = match self.deletion_request.take() {
+            Some(request) => request,
+            None => return false,
+        };
+        match (key, window.mark_pane.as_mut()) {
+            (Key::Char('y'), Some(pane)) => {
+                self.start_deletion(pane, request.mode, traversal, format)
             }
-            entries_deleted = self.delete_entries_in_traversal(index, traversal);
+            _ => self.message = Some("Nothing was deleted".into()),
         }
-        Ok(entries_deleted)
+        true
     }
 
     pub fn delete_entries_in_traversal(
@@ -301,13 +451,22 @@ impl AppState {
             .neighbors_directed(index, Direction::Incoming)
             .next()
             .expect("us being unable to delete the root index");
+        let removed = traversal
+            .tree
+            .node_weight(index)
+            .map(|w| (w.size_on_disk, w.apparent_size))
+            .unwrap_or_default();
         let mut bfs = Bfs::new(&traversal.tree, index);
         while let Some(nx) = bfs.next(&traversal.tree) {
             traversal.tree.remove_node(nx);
+            self.expanded.remove(&nx);
+            self.history.forget(nx);
+            traversal.hard_links.forget(nx);
+            self.named_bookmarks.retain(|_, idx| *idx != nx);
             traversal.entries_traversed -= 1;
             entries_deleted += 1;
         }
-        self.entries = sorted_entries(&traversal.tree, self.root, self.sorting);
+        self.entries = self.entries_at(&traversal.tree, self.root);
         if traversal.tree.node_weight(self.root).is_none() {
             self.set_root(traversal.root_index, traversal);
         }
@@ -316,28 +475,31 @@ impl AppState {
             .and_then(|selected| self.entries.iter().find(|e| e.index == selected))
             .is_none()
         {
-            self.selected = self.entries.get(0).map(|e| e.index);
+            self.selected = self.entries.first().map(|e| e.index);
         }
-        self.recompute_sizes_recursively(parent_idx, traversal);
+        self.subtract_sizes_recursively(parent_idx, removed, entries_deleted as u64, traversal);
         entries_deleted
     }
 
     fn set_root(&mut self, root: TreeIndex, traversal: &Traversal) {
         self.root = root;
-        self.entries = sorted_entries(&traversal.tree, root, self.sorting);
+        self.entries = self.entries_at(&traversal.tree, root);
     }
 
-    fn recompute_sizes_recursively(&mut self, mut index: TreeIndex, traversal: &mut Traversal) {
+    /// Remove the sizes and the `num_entries` of a deleted entry from all of its ancestors, starting at `index`.
+    /// Sizes can't be recomputed from the remaining children as directories may account for their own size.
+    fn subtract_sizes_recursively(
+        &mut self,
+        mut index: TreeIndex,
+        (size_on_disk, apparent_size): (u128, u128),
+        num_entries: u64,
+        traversal: &mut Traversal,
+    ) {
         loop {
-            traversal
-                .tree
-                .node_weight_mut(index)
-                .expect("valid index")
-                .size = traversal
-                .tree
-                .neighbors_directed(index, Direction::Outgoing)
-                .filter_map(|idx| traversal.tree.node_weight(idx).map(|w| w.size))
-                .sum();
+            let entry = traversal.tree.node_weight_mut(index).expect("valid index");
+            entry.size_on_disk = entry.size_on_disk.saturating_sub(size_on_disk);
+            entry.apparent_size = entry.apparent_size.saturating_sub(apparent_size);
+            entry.entry_count = entry.entry_count.saturating_sub(num_entries);
             match traversal
                 .tree
                 .neighbors_directed(index, Direction::Incoming)
@@ -347,13 +509,9 @@ impl AppState {
                 Some(parent) => index = parent,
             }
         }
-        traversal.total_bytes = traversal
-            .tree
-            .node_weight(traversal.root_index)
-            .map(|w| w.size);
     }
 
-    fn mark_entry_by_index(
+    pub fn mark_entry_by_index(
         &mut self,
         index: TreeIndex,
         mode: MarkEntryMode,
@@ -393,81 +551,77 @@ impl AppState {
         }
     }
 
-    pub fn mark_all_entries(
-        &mut self,
-        mode: MarkEntryMode,
-        window: &mut MainWindow,
-        traversal: &Traversal,
-    ) {
-        for index in self.entries.iter().map(|e| e.index).collect::<Vec<_>>() {
-            self.mark_entry_by_index(index, mode, window, traversal);
-        }
+    /// Mark all entries of the current directory, or unmark them if all of them are marked already.
+    pub fn mark_or_unmark_all(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        let marked = window.mark_pane.as_ref().map(|pane| pane.marked());
+        let all_marked = self
+            .entries
+            .iter()
+            .filter(|e| e.depth == 0)
+            .all(|e| marked.is_some_and(|marked| marked.contains_key(&e.index)));
+        let mode = if all_marked {
+            MarkEntryMode::Toggle
+        } else {
+            MarkEntryMode::MarkForDeletion
+        };
+        self.mark_all_entries(mode, window, traversal);
     }
-}
 
-fn into_error_count(res: Result<(), io::Error>) -> usize {
-    match res.map_err(io_err_to_usize) {
-        Ok(_) => 0,
-        Err(c) => c,
+    /// Start selecting a range of entries from the selected one, or mark the range if one is being selected.
+    pub fn toggle_visual_range(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        if self.visual_anchor.is_some() {
+            self.mark_visual_range(window, traversal);
+        } else {
+            self.visual_anchor = self.selected;
+            self.message = Some("Move to select a range, and mark it with 'V'".into());
+        }
     }
-}
 
-fn io_err_to_usize(err: io::Error) -> usize {
-    if err.kind() == io::ErrorKind::NotFound {
-        0
-    } else {
-        1
+    /// The positions in `entries` of the range of entries being selected, if they are still shown.
+    pub fn visual_range(&self) -> Option<RangeInclusive<usize>> {
+        let position = |index| self.entries.iter().position(|e| e.index == index);
+        let anchor = position(self.visual_anchor?)?;
+        let selected = position(self.selected?)?;
+        Some(anchor.min(selected)..=anchor.max(selected))
     }
-}
 
-// TODO: could use jwalk for this
-// see https://github.com/Byron/dua-cli/issues/43
-fn delete_directory_recursively(path: PathBuf) -> Result<(), usize> {
-    let mut files_or_dirs = vec![path];
-    let mut dirs = Vec::new();
-    let mut num_errors = 0;
-    while let Some(path) = files_or_dirs.pop() {
-        let assume_symlink_to_try_deletion = true;
-        let is_symlink = path
-            .symlink_metadata()
-            .map(|m| m.file_type().is_symlink())
-            .unwrap_or(assume_symlink_to_try_deletion);
-        if is_symlink {
-            // do not follow symlinks
-            num_errors += into_error_count(fs::remove_file(&path));
-            continue;
+    pub fn mark_visual_range(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        let indices: Vec<_> = self
+            .visual_range()
+            .map(|range| self.entries[range].iter().map(|e| e.index).collect())
+            .unwrap_or_default();
+        self.visual_anchor = None;
+        for index in &indices {
+            self.mark_entry_by_index(*index, MarkEntryMode::MarkForDeletion, window, traversal);
         }
-        match fs::read_dir(&path) {
-            Ok(iterator) => {
-                dirs.push(path);
-                for entry in iterator {
-                    match entry.map_err(io_err_to_usize) {
-                        Ok(entry) => files_or_dirs.push(entry.path()),
-                        Err(c) => num_errors += c,
-                    }
-                }
-            }
-            // Err(ref e) if e.kind() == io::ErrorKind::NotADirectory => {
-            //     // assume file, save IOps
-            //     num_errors += into_error_count(fs::remove_file(path));
-            //     continue;
-            // }
-            Err(_) => {
-                // TODO: Reintroduce commented code once the `io_error_more` feature is stable
-                // num_errors += 1;
-                num_errors += into_error_count(fs::remove_file(path));
-                continue;
-            }
-        };
+        self.message = Some(format!(
+            "Marked {} entr{}",
+            indices.len(),
+            if indices.len() == 1 { "y" } else { "ies" }
+        ));
     }
 
-    for dir in dirs.into_iter().rev() {
-        num_errors += into_error_count(fs::remove_dir(&dir).or_else(|_| fs::remove_file(dir)));
+    /// Unmark the entries below the current directory, no matter how deeply they are nested.
+    pub fn unmark_directory(&mut self, window: &mut MainWindow, traversal: &Traversal) {
+        if let Some(pane) = window.mark_pane.take() {
+            window.mark_pane = pane.unmark_below(self.root, &traversal.tree);
+        }
     }
 
-    if num_errors == 0 {
-        Ok(())
-    } else {
-        Err(num_errors)
+    pub fn mark_all_entries(
+        &mut self,
+        mode: MarkEntryMode,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) {
+        for index in self
+            .entries
+            .iter()
+            .filter(|e| e.depth == 0)
+            .map(|e| e.index)
+            .collect::<Vec<_>>()
+        {
+            self.mark_entry_by_index(index, mode, window, traversal);
+        }
     }
 }
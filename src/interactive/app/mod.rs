@@ -1,12 +1,28 @@
+mod bindings;
 mod bytevis;
+mod command;
 mod common;
+mod deletion;
 mod eventloop;
+mod goto;
 mod handlers;
+mod navigation;
+mod rules;
+mod script;
+mod search;
 
+pub use bindings::*;
 pub use bytevis::*;
+pub use command::*;
 pub use common::*;
+pub use deletion::*;
 pub use eventloop::*;
+pub use goto::*;
 pub use handlers::*;
+pub use navigation::*;
+pub use rules::*;
+pub use script::*;
+pub use search::*;
 
 #[cfg(test)]
 mod tests;
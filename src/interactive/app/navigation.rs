@@ -0,0 +1,124 @@
+use crate::interactive::AppState;
+use crosstermion::input::Key;
+use dua::traverse::{Traversal, TreeIndex};
+use itertools::Itertools;
+
+/// The amount of directories remembered in either direction of the history.
+const MAX_HISTORY: usize = 100;
+
+/// What to do with the bookmark named by the next key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookmarkRequest {
+    Set,
+    Jump,
+}
+
+/// The directories visited before and after the current one, to go back and forth between them.
+#[derive(Default, Debug, Clone)]
+pub struct History {
+    back: Vec<TreeIndex>,
+    forward: Vec<TreeIndex>,
+}
+
+impl History {
+    /// Remember that `root` is left for another directory, which makes going forward impossible.
+    pub fn leave(&mut self, root: TreeIndex) {
+        if self.back.last() != Some(&root) {
+            self.back.push(root);
+            if self.back.len() > MAX_HISTORY {
+                self.back.remove(0);
+            }
+        }
+        self.forward.clear();
+    }
+
+    /// Return the directory visited before `current`, if there is one.
+    pub fn back(&mut self, current: TreeIndex) -> Option<TreeIndex> {
+        let previous = self.back.pop()?;
+        self.forward.push(current);
+        Some(previous)
+    }
+
+    /// Return the directory visited after `current` before going back to it, if there is one.
+    pub fn forward(&mut self, current: TreeIndex) -> Option<TreeIndex> {
+        let next = self.forward.pop()?;
+        self.back.push(current);
+        Some(next)
+    }
+
+    /// Forget `index` as it was deleted.
+    pub fn forget(&mut self, index: TreeIndex) {
+        self.back.retain(|idx| *idx != index);
+        self.forward.retain(|idx| *idx != index);
+    }
+}
+
+impl AppState {
+    /// Wait for the name of a bookmark to set or jump to, which is the next key typed.
+    pub fn request_bookmark(&mut self, request: BookmarkRequest) {
+        self.bookmark_request = Some(request);
+        self.message = Some(match request {
+            BookmarkRequest::Set => "Type a key to bookmark the selected entry with".into(),
+            BookmarkRequest::Jump if self.named_bookmarks.is_empty() => {
+                self.bookmark_request = None;
+                "There are no bookmarks yet".into()
+            }
+            BookmarkRequest::Jump => format!(
+                "Jump to bookmark: {}",
+                self.named_bookmarks.keys().join(" ")
+            ),
+        });
+    }
+
+    /// Handle `key` as the name of a requested bookmark, and return `true` if it was consumed.
+    pub fn process_bookmark_input(&mut self, key: Key, traversal: &Traversal) -> bool {
+        let request = match self.bookmark_request.take() {
+            Some(request) => request,
+            None => return false,
+        };
+        let name = match key {
+            Key::Char(c) if !c.is_control() => c,
+            _ => return true,
+        };
+        match request {
+            BookmarkRequest::Set => match self.selected {
+                Some(selected) => {
+                    self.named_bookmarks.insert(name, selected);
+                    self.message = Some(format!("Bookmarked as '{}'", name));
+                }
+                None => self.message = Some("Nothing selected to bookmark".into()),
+            },
+            BookmarkRequest::Jump => match self.named_bookmarks.get(&name).copied() {
+                Some(index) => self.select_in_tree(index, traversal),
+                None => self.message = Some(format!("There is no bookmark '{}'", name)),
+            },
+        }
+        true
+    }
+
+    pub fn go_back(&mut self, traversal: &Traversal) {
+        match self.history.back(self.root) {
+            Some(root) => self.visit(root, traversal),
+            None => self.message = Some("There is no directory to go back to".into()),
+        }
+    }
+
+    pub fn go_forward(&mut self, traversal: &Traversal) {
+        match self.history.forward(self.root) {
+            Some(root) => self.visit(root, traversal),
+            None => self.message = Some("There is no directory to go forward to".into()),
+        }
+    }
+
+    /// Show the entries of `root` without recording it in the history, selecting the entry that was selected last.
+    pub fn visit(&mut self, root: TreeIndex, traversal: &Traversal) {
+        self.root = root;
+        self.entries = self.entries_at(&traversal.tree, root);
+        self.selected = self
+            .bookmarks
+            .get(&root)
+            .copied()
+            .filter(|selected| self.entries.iter().any(|e| e.index == *selected))
+            .or_else(|| self.entries.first().map(|e| e.index));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::interactive::{widgets::MainWindow, AppState, Pattern, PatternKind, SizeFilter};
+use anyhow::{anyhow, bail, Result};
+use crosstermion::input::Key;
+use dua::{
+    traverse::{SizeKind, Traversal, Tree, TreeIndex},
+    ByteFormat,
+};
+use petgraph::Direction;
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+/// A condition on the time entries were last modified at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeFilter {
+    Older(Duration),
+    Newer(Duration),
+}
+
+impl AgeFilter {
+    /// Return `true` if an entry modified at `mtime` matches, as seen at `now`.
+    pub fn matches(self, mtime: Option<SystemTime>, now: SystemTime) -> bool {
+        let age = match mtime.map(|mtime| now.duration_since(mtime).unwrap_or_default()) {
+            Some(age) => age,
+            None => return false,
+        };
+        match self {
+            AgeFilter::Older(duration) => age > duration,
+            AgeFilter::Newer(duration) => age < duration,
+        }
+    }
+}
+
+/// Parse durations like `12h`, `30d`, `2w` or `1y`.
+fn parse_age(s: &str) -> Result<Duration> {
+    let unit_pos = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow!("The age '{}' needs a unit like 'h', 'd', 'w' or 'y'", s))?;
+    let (amount, unit) = s.split_at(unit_pos);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| anyhow!("Invalid age '{}', expected something like '30d'", s))?;
+    let hours: u64 = match unit {
+        "h" => 1,
+        "d" => 24,
+        "w" => 24 * 7,
+        "y" => 24 * 365,
+        _ => bail!(
+            "Unknown unit of age '{}', expected 'h', 'd', 'w' or 'y'",
+            unit
+        ),
+    };
+    amount
+        .checked_mul(hours * 60 * 60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| anyhow!("The age '{}' is too large", s))
+}
+
+/// What files to mark, like `*.core >100MB older 30d`.
+///
+/// Every part is optional, but at least one is needed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MarkRule {
+    /// A glob the names of files have to match.
+    pub glob: Option<String>,
+    pub size: Option<SizeFilter>,
+    pub age: Option<AgeFilter>,
+}
+
+impl FromStr for MarkRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rule = MarkRule::default();
+        let mut words = s.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "older" | "newer" => {
+                    let age = parse_age(
+                        words
+                            .next()
+                            .ok_or_else(|| anyhow!("'{}' needs an age like '30d'", word))?,
+                    )?;
+                    rule.age = Some(if word == "older" {
+                        AgeFilter::Older(age)
+                    } else {
+                        AgeFilter::Newer(age)
+                    });
+                }
+                size if size.starts_with('>') || size.starts_with('<') => {
+                    rule.size = Some(size.parse()?)
+                }
+                glob if rule.glob.is_none() => rule.glob = Some(glob.to_owned()),
+                _ => bail!("Only one glob can be used, but got '{}' as well", word),
+            }
+        }
+        if rule == MarkRule::default() {
+            bail!("Rules need a glob, a size like '>100MB' or an age like 'older 30d'");
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for MarkRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(glob) = &self.glob {
+            parts.push(glob.clone());
+        }
+        if let Some(size) = self.size {
+            parts.push(size.to_string());
+        }
+        match self.age {
+            Some(AgeFilter::Older(age)) => parts.push(format!("older {}", format_age(age))),
+            Some(AgeFilter::Newer(age)) => parts.push(format!("newer {}", format_age(age))),
+            None => {}
+        }
+        f.write_str(&parts.join(" "))
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let hours = age.as_secs() / (60 * 60);
+    let (days, remaining_hours) = (hours / 24, hours % 24);
+    if remaining_hours == 0 {
+        format!("{}d", days)
+    } else {
+        format!("{}h", hours)
+    }
+}
+
+impl MarkRule {
+    /// Return all files below `root` that match this rule, as seen at `now`.
+    ///
+    /// Directories are never matched as their size sums up the files in them.
+    pub fn matching_files(
+        &self,
+        tree: &Tree,
+        root: TreeIndex,
+        size_kind: SizeKind,
+        now: SystemTime,
+    ) -> Result<Vec<TreeIndex>> {
+        let pattern = self
+            .glob
+            .as_deref()
+            .map(|glob| Pattern::new(PatternKind::Glob, glob))
+            .transpose()?;
+        let mut matches = Vec::new();
+        let mut stack: Vec<_> = tree.neighbors_directed(root, Direction::Outgoing).collect();
+        while let Some(index) = stack.pop() {
+            let len = stack.len();
+            stack.extend(tree.neighbors_directed(index, Direction::Outgoing));
+            if stack.len() != len {
+                continue;
+            }
+            let entry = &tree[index];
+            let is_match = pattern.iter().all(|p| p.is_match(&entry.name))
+                && self.size.iter().all(|s| s.matches(entry.size(size_kind)))
+                && self.age.iter().all(|a| a.matches(entry.mtime, now));
+            if is_match && !entry.is_dir {
+                matches.push(index);
+            }
+        }
+        Ok(matches)
+    }
+}
+
+/// Files matching a rule that are about to be marked once confirmed.
+#[derive(Debug, Clone)]
+pub struct MarksPreview {
+    pub indices: Vec<TreeIndex>,
+}
+
+impl AppState {
+    /// Find all files below the current directory that match `rule` and ask to mark them.
+    pub fn preview_marks(
+        &mut self,
+        rule: &MarkRule,
+        format: ByteFormat,
+        traversal: &Traversal,
+    ) -> Result<()> {
+        let tree = &traversal.tree;
+        let indices = rule.matching_files(tree, self.root, self.size_kind, SystemTime::now())?;
+        if indices.is_empty() {
+            self.message = Some(format!("No files match '{}'", rule));
+            return Ok(());
+        }
+        let size: u128 = indices
+            .iter()
+            .map(|idx| tree[*idx].size(self.size_kind))
+            .sum();
+        self.message = Some(format!(
+            "Mark {} {} with {} matching '{}'? Press 'y' to mark them",
+            indices.len(),
+            if indices.len() == 1 { "file" } else { "files" },
+            format.display(size).to_string().trim(),
+            rule
+        ));
+        self.marks_preview = Some(MarksPreview { indices });
+        Ok(())
+    }
+
+    /// Mark the previewed files if `key` confirms it, and return `true` if there was a preview to handle the key.
+    pub fn process_marks_preview_input(
+        &mut self,
+        key: Key,
+        window: &mut MainWindow,
+        traversal: &Traversal,
+    ) -> bool {
+        let preview = match self.marks_preview.take() {
+            Some(preview) => preview,
+            None => return false,
+        };
+        match key {
+            Key::Char('y') | Key::Char('Y') => {
+                let mut pane = window.mark_pane.take().unwrap_or_default();
+                for index in &preview.indices {
+                    pane = pane
+                        .toggle_index(*index, &traversal.tree, false, false)
+                        .unwrap_or_default();
+                }
+                window.mark_pane = Some(pane).filter(|pane| !pane.marked().is_empty());
+                self.message = Some(format!(
+                    "Marked {} {}",
+                    preview.indices.len(),
+                    if preview.indices.len() == 1 {
+                        "file"
+                    } else {
+                        "files"
+                    }
+                ));
+            }
+            _ => self.message = Some("Nothing was marked".into()),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_parsed_in_any_order() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert_eq!(
+            "*.core >100MB older 30d".parse::<MarkRule>().unwrap(),
+            MarkRule {
+                glob: Some("*.core".into()),
+                size: Some(SizeFilter::Larger(100_000_000)),
+                age: Some(AgeFilter::Older(day * 30)),
+            }
+        );
+        assert_eq!(
+            "newer 2w <1KB".parse::<MarkRule>().unwrap(),
+            MarkRule {
+                glob: None,
+                size: Some(SizeFilter::Smaller(1000)),
+                age: Some(AgeFilter::Newer(day * 14)),
+            }
+        );
+        for (rule, error) in [
+            (
+                "",
+                "Rules need a glob, a size like '>100MB' or an age like 'older 30d'",
+            ),
+            (
+                "*.a *.b",
+                "Only one glob can be used, but got '*.b' as well",
+            ),
+            ("older", "'older' needs an age like '30d'"),
+            (
+                "older 30",
+                "The age '30' needs a unit like 'h', 'd', 'w' or 'y'",
+            ),
+            (
+                "newer 3m",
+                "Unknown unit of age 'm', expected 'h', 'd', 'w' or 'y'",
+            ),
+            (
+                "older 18446744073709551615y",
+                "The age '18446744073709551615y' is too large",
+            ),
+        ] {
+            assert_eq!(
+                rule.parse::<MarkRule>().unwrap_err().to_string(),
+                error,
+                "{rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn age_filters_compare_with_the_time_of_modification() {
+        let now = SystemTime::now();
+        let hour = Duration::from_secs(60 * 60);
+        let two_hours_ago = Some(now - hour * 2);
+        assert!(AgeFilter::Older(hour).matches(two_hours_ago, now));
+        assert!(!AgeFilter::Newer(hour).matches(two_hours_ago, now));
+        assert!(AgeFilter::Newer(hour * 3).matches(two_hours_ago, now));
+        assert!(
+            !AgeFilter::Older(hour).matches(None, now),
+            "entries without modification time never match"
+        );
+    }
+}
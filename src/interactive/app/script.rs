@@ -0,0 +1,140 @@
+use crate::interactive::{
+    widgets::{MarkMode, MarkPane},
+    AppState,
+};
+use dua::ByteFormat;
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The file scripts deleting marked entries are written to if none was given with `--dry-run`.
+pub const DEFAULT_SCRIPT_PATH: &str = "dua-deletion.sh";
+
+/// Quote `path` as a single word for POSIX shells.
+///
+/// Names are single-quoted, and bytes that aren't valid UTF-8 are produced with `printf` in octal
+/// so the script stays readable text.
+pub fn shell_quote(path: &Path) -> String {
+    let bytes = bytes_of(path);
+    if bytes.is_empty() {
+        return "''".into();
+    }
+    let mut quoted = String::new();
+    let mut invalid = Vec::new();
+    for chunk in bytes.utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            push_printf(&mut quoted, &mut invalid);
+            quoted.push('\'');
+            quoted.push_str(&chunk.valid().replace('\'', r"'\''"));
+            quoted.push('\'');
+        }
+        invalid.extend_from_slice(chunk.invalid());
+    }
+    push_printf(&mut quoted, &mut invalid);
+    quoted
+}
+
+/// Append a command substitution producing the `bytes` in octal to `quoted`, and clear them.
+fn push_printf(quoted: &mut String, bytes: &mut Vec<u8>) {
+    if bytes.is_empty() {
+        return;
+    }
+    quoted.push_str("\"$(printf '");
+    for byte in bytes.drain(..) {
+        write!(quoted, "\\{:03o}", byte).expect("writing to a string to succeed");
+    }
+    quoted.push_str("')\"");
+}
+
+/// Return the bytes of `path`, which are the ones of its lossy UTF-8 form on other platforms than unix.
+#[cfg(unix)]
+pub fn bytes_of(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn bytes_of(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(path) => Cow::Borrowed(path.as_bytes()),
+        Cow::Owned(path) => Cow::Owned(path.into_bytes()),
+    }
+}
+
+/// Return the path made of `bytes` as returned by [`bytes_of()`].
+#[cfg(unix)]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+    std::ffi::OsString::from_vec(bytes).into()
+}
+
+#[cfg(not(unix))]
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+impl AppState {
+    /// Write a script deleting or trashing the entries marked in `pane` according to `mode`, instead of doing so,
+    /// to the file given with `--dry-run` or the default one, unless it exists already.
+    pub fn write_deletion_script(&mut self, pane: &MarkPane, mode: MarkMode, format: ByteFormat) {
+        let path = self
+            .dry_run
+            .clone()
+            .unwrap_or_else(|| DEFAULT_SCRIPT_PATH.into());
+        let res = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map(io::BufWriter::new)
+            .and_then(|mut out| {
+                pane.write_script(&mut out, mode, self.size_kind, format)?;
+                out.flush()
+            });
+        self.message = Some(match res {
+            Ok(()) => format!(
+                "Wrote a script {} the marked entries to '{}' for review",
+                match mode {
+                    MarkMode::Delete => "deleting",
+                    #[cfg(feature = "trash-move")]
+                    MarkMode::Trash => "trashing",
+                },
+                path.display()
+            ),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => format!(
+                "'{}' exists already and was left as it is, remove it to write a new script",
+                path.display()
+            ),
+            Err(err) => format!("Could not write a script to '{}': {}", path.display(), err),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_are_quoted_as_single_words() {
+        assert_eq!(shell_quote(Path::new("")), "''");
+        assert_eq!(shell_quote(Path::new("/tmp/a b")), "'/tmp/a b'");
+        assert_eq!(
+            shell_quote(Path::new("it's $HOME\n`x`")),
+            "'it'\\''s $HOME\n`x`'"
+        );
+        assert_eq!(shell_quote(Path::new("-rf")), "'-rf'");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn bytes_that_are_not_utf8_are_escaped() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        assert_eq!(
+            shell_quote(Path::new(OsStr::from_bytes(b"a\xff\xfeb"))),
+            "'a'\"$(printf '\\377\\376')\"'b'"
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use crate::interactive::{AppState, EntryDataBundle};
+use anyhow::Result;
+use crosstermion::input::Key;
+use dua::traverse::{Traversal, TreeIndex};
+use petgraph::Direction;
+use std::path::Path;
+
+/// How the text of a search is interpreted.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    #[default]
+    Substring,
+    Glob,
+    Regex,
+}
+
+impl PatternKind {
+    pub fn cycle(&mut self) {
+        use PatternKind::*;
+        *self = match self {
+            Substring => Glob,
+            Glob => Regex,
+            Regex => Substring,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PatternKind::Substring => "substring",
+            PatternKind::Glob => "glob",
+            PatternKind::Regex => "regex",
+        }
+    }
+}
+
+/// A pattern to match the names of entries with.
+///
+/// Matching ignores case unless the pattern contains an uppercase character.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Substring { needle: String, ignore_case: bool },
+    Glob(globset::GlobMatcher),
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind, text: &str) -> Result<Self> {
+        let ignore_case = !text.chars().any(char::is_uppercase);
+        Ok(match kind {
+            PatternKind::Substring => Pattern::Substring {
+                needle: if ignore_case {
+                    text.to_lowercase()
+                } else {
+                    text.to_owned()
+                },
+                ignore_case,
+            },
+            PatternKind::Glob => Pattern::Glob(
+                globset::GlobBuilder::new(text)
+                    .case_insensitive(ignore_case)
+                    .build()?
+                    .compile_matcher(),
+            ),
+            PatternKind::Regex => Pattern::Regex(
+                regex::RegexBuilder::new(text)
+                    .case_insensitive(ignore_case)
+                    .build()?,
+            ),
+        })
+    }
+
+    pub fn is_match(&self, name: &Path) -> bool {
+        match self {
+            Pattern::Substring {
+                needle,
+                ignore_case,
+            } => {
+                let name = name.to_string_lossy();
+                if *ignore_case {
+                    name.to_lowercase().contains(needle.as_str())
+                } else {
+                    name.contains(needle.as_str())
+                }
+            }
+            Pattern::Glob(glob) => glob.is_match(name),
+            Pattern::Regex(regex) => regex.is_match(&name.to_string_lossy()),
+        }
+    }
+}
+
+/// Where to look for entries with matching names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchScope {
+    /// The entries of the directory that is currently shown.
+    Directory,
+    /// All entries of the traversal.
+    Tree,
+}
+
+/// A search for entries by name, which is edited as long as `is_editing` is set.
+pub struct Search {
+    pub scope: SearchScope,
+    pub kind: PatternKind,
+    pub text: String,
+    pub is_editing: bool,
+    /// The reason the `text` can't be used as pattern.
+    pub error: Option<String>,
+    pattern: Option<Pattern>,
+    /// The root and selection before the search started, to return to if it is cancelled.
+    origin: (TreeIndex, Option<TreeIndex>),
+    /// All matching entries of the tree if the scope is [`SearchScope::Tree`].
+    tree_matches: Vec<TreeIndex>,
+    /// The position of the last visited entry in `tree_matches`.
+    tree_position: Option<usize>,
+}
+
+impl Search {
+    pub fn new(scope: SearchScope, root: TreeIndex, selected: Option<TreeIndex>) -> Self {
+        Search {
+            scope,
+            kind: PatternKind::default(),
+            text: String::new(),
+            is_editing: true,
+            error: None,
+            pattern: None,
+            origin: (root, selected),
+            tree_matches: Vec::new(),
+            tree_position: None,
+        }
+    }
+
+    /// Return `true` if there is a valid pattern and `name` matches it.
+    pub fn is_match(&self, name: &Path) -> bool {
+        self.pattern.as_ref().is_some_and(|p| p.is_match(name))
+    }
+
+    /// The amount of matches, among `entries` if the search is limited to a directory.
+    pub fn num_matches(&self, entries: &[EntryDataBundle]) -> usize {
+        match self.scope {
+            SearchScope::Directory => entries
+                .iter()
+                .filter(|e| self.is_match(&e.data.name))
+                .count(),
+            SearchScope::Tree => self.tree_matches.len(),
+        }
+    }
+
+    /// A description of the search for display.
+    pub fn title(&self) -> String {
+        format!(
+            "{} ({})",
+            match self.scope {
+                SearchScope::Directory => "Search",
+                SearchScope::Tree => "Search everywhere",
+            },
+            self.kind.name()
+        )
+    }
+
+    fn update(&mut self, traversal: &Traversal) {
+        self.error = None;
+        self.pattern = match self.text.as_str() {
+            "" => None,
+            text => match Pattern::new(self.kind, text) {
+                Ok(pattern) => Some(pattern),
+                Err(err) => {
+                    self.error = Some(err.to_string());
+                    None
+                }
+            },
+        };
+        self.tree_position = None;
+        self.tree_matches.clear();
+        if let (SearchScope::Tree, Some(pattern)) = (self.scope, &self.pattern) {
+            let tree = &traversal.tree;
+            self.tree_matches = tree
+                .node_indices()
+                .filter(|idx| *idx != traversal.root_index)
+                .filter(|idx| pattern.is_match(&tree[*idx].name))
+                .collect();
+        }
+    }
+}
+
+impl AppState {
+    pub fn start_search(&mut self, scope: SearchScope) {
+        self.search = Some(Search::new(scope, self.root, self.selected));
+    }
+
+    /// Edit the search with `key` if it is being edited, and return `true` if the key was used for that.
+    pub fn process_search_input(&mut self, key: Key, traversal: &Traversal) -> bool {
+        let search = match self.search.as_mut() {
+            Some(search) if search.is_editing => search,
+            _ => return false,
+        };
+        match key {
+            Key::Esc => {
+                let (root, selected) = search.origin;
+                self.search = None;
+                if traversal.tree.node_weight(root).is_some() {
+                    self.root = root;
+                    self.entries = self.entries_at(&traversal.tree, root);
+                    self.selected = selected;
+                }
+                return true;
+            }
+            Key::Char('\n') => {
+                search.is_editing = false;
+                match search.scope {
+                    _ if search.pattern.is_none() => self.search = None,
+                    SearchScope::Directory => {}
+                    SearchScope::Tree => self.select_next_match(true, traversal),
+                }
+                return true;
+            }
+            Key::Char('\t') => search.kind.cycle(),
+            Key::Backspace => {
+                search.text.pop();
+            }
+            Key::Ctrl('u') => search.text.clear(),
+            Key::Char(c) => search.text.push(c),
+            _ => return false,
+        };
+        search.update(traversal);
+        if search.scope == SearchScope::Directory {
+            let origin = search.origin.1;
+            self.select_first_match_in_directory(origin);
+        }
+        true
+    }
+
+    /// Select the next match in the current search, or the previous one if `forward` is `false`.
+    pub fn select_next_match(&mut self, forward: bool, traversal: &Traversal) {
+        let search = match self.search.as_mut() {
+            Some(search) if search.pattern.is_some() => search,
+            _ => {
+                self.message = Some("There is no search".into());
+                return;
+            }
+        };
+        match search.scope {
+            SearchScope::Directory => {
+                let entries = &self.entries;
+                let current = self
+                    .selected
+                    .and_then(|selected| entries.iter().position(|e| e.index == selected));
+                let next = wrapping_positions(entries.len(), current, forward)
+                    .find(|pos| search.is_match(&entries[*pos].data.name));
+                match next {
+                    Some(pos) => self.selected = Some(entries[pos].index),
+                    None => self.message = Some("No matches".into()),
+                }
+            }
+            SearchScope::Tree => {
+                search
+                    .tree_matches
+                    .retain(|idx| traversal.tree.node_weight(*idx).is_some());
+                let num_matches = search.tree_matches.len();
+                let next = wrapping_positions(num_matches, search.tree_position, forward).next();
+                search.tree_position = next;
+                match next {
+                    Some(pos) => {
+                        let index = search.tree_matches[pos];
+                        self.select_in_tree(index, traversal);
+                        self.message = Some(format!("Match {} of {}", pos + 1, num_matches));
+                    }
+                    None => self.message = Some("No matches".into()),
+                }
+            }
+        }
+    }
+
+    fn select_first_match_in_directory(&mut self, origin: Option<TreeIndex>) {
+        let search = match self.search.as_ref() {
+            Some(search) => search,
+            None => return,
+        };
+        let entries = &self.entries;
+        let start = origin
+            .and_then(|origin| entries.iter().position(|e| e.index == origin))
+            .map(|pos| pos + entries.len() - 1);
+        self.selected = wrapping_positions(entries.len(), start, true)
+            .find(|pos| search.is_match(&entries[*pos].data.name))
+            .map(|pos| entries[pos].index)
+            .or(origin);
+    }
+
+    /// Show the directory containing `index` and select it.
+    pub fn select_in_tree(&mut self, index: TreeIndex, traversal: &Traversal) {
+        let parent = match traversal
+            .tree
+            .neighbors_directed(index, Direction::Incoming)
+            .next()
+        {
+            Some(parent) => parent,
+            None => return,
+        };
+        if parent != self.root {
+            self.history.leave(self.root);
+        }
+        self.root = parent;
+        self.entries = self.entries_at(&traversal.tree, parent);
+        self.selected = Some(index);
+        self.bookmarks.insert(parent, index);
+    }
+}
+
+/// All positions in `0..len` once, starting after `current` and wrapping around, backwards unless `forward` is set.
+fn wrapping_positions(
+    len: usize,
+    current: Option<usize>,
+    forward: bool,
+) -> impl Iterator<Item = usize> {
+    let start = match (current, forward) {
+        (Some(current), true) => current + 1,
+        (Some(current), false) => current + len.saturating_sub(1),
+        (None, true) => 0,
+        (None, false) => len.saturating_sub(1),
+    };
+    (0..len)
+        .map(move |step| {
+            if forward {
+                start + step
+            } else {
+                start + len * 2 - step
+            }
+        })
+        .map(move |pos| pos % len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_ignore_case_unless_they_contain_uppercase_characters() -> Result<()> {
+        for (kind, text, name, expected) in [
+            (PatternKind::Substring, "ar", "Cargo.toml", true),
+            (PatternKind::Substring, "CAR", "Cargo.toml", false),
+            (PatternKind::Glob, "*.toml", "Cargo.TOML", true),
+            (PatternKind::Glob, "*.TOML", "Cargo.toml", false),
+            (PatternKind::Regex, "^c.*l$", "Cargo.toml", true),
+            (PatternKind::Regex, "^C.*L$", "Cargo.toml", false),
+        ] {
+            assert_eq!(
+                Pattern::new(kind, text)?.is_match(Path::new(name)),
+                expected,
+                "{:?} {} {}",
+                kind,
+                text,
+                name
+            );
+        }
+        assert!(Pattern::new(PatternKind::Regex, "(").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn positions_wrap_around_in_both_directions() {
+        let positions =
+            |current, forward| wrapping_positions(3, current, forward).collect::<Vec<_>>();
+        assert_eq!(positions(None, true), vec![0, 1, 2]);
+        assert_eq!(positions(Some(1), true), vec![2, 0, 1]);
+        assert_eq!(positions(None, false), vec![2, 1, 0]);
+        assert_eq!(positions(Some(1), false), vec![0, 2, 1]);
+        assert_eq!(positions(Some(0), false), vec![2, 1, 0]);
+        assert!(wrapping_positions(0, None, true).next().is_none());
+    }
+}
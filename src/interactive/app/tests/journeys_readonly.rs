@@ -1,4 +1,5 @@
 use anyhow::Result;
+use crosstermion::input::{Event, Key};
 use pretty_assertions::assert_eq;
 use std::ffi::OsString;
 
@@ -10,7 +11,7 @@ use crate::interactive::{
         },
         FIXTURE_PATH,
     },
-    SortMode,
+    SortMode, TerminalApp,
 };
 
 #[test]
@@ -176,9 +177,10 @@ fn simple_user_journey_read_only() -> Result<()> {
                 "it marks only a single node",
             );
             assert!(
-                app.window.mark_pane.as_ref().map_or(false, |p| p
-                    .marked()
-                    .contains_key(&previously_selected_index)),
+                app.window
+                    .mark_pane
+                    .as_ref()
+                    .is_some_and(|p| p.marked().contains_key(&previously_selected_index)),
                 "it marks the selected node"
             );
             assert_eq!(
@@ -216,9 +218,10 @@ fn simple_user_journey_read_only() -> Result<()> {
             );
 
             assert!(
-                app.window.mark_pane.as_ref().map_or(false, |p| p
-                    .marked()
-                    .contains_key(&previously_selected_index)),
+                app.window
+                    .mark_pane
+                    .as_ref()
+                    .is_some_and(|p| p.marked().contains_key(&previously_selected_index)),
                 "it leaves the first selected entry marked"
             );
         }
@@ -274,3 +277,546 @@ fn simple_user_journey_read_only() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn searching_entries_by_name() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let selected_name = |app: &crate::interactive::TerminalApp| {
+        node_by_index(app, app.state.selected.expect("a selection"))
+            .name
+            .to_string_lossy()
+            .into_owned()
+    };
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(selected_name(&app), "dir", "the biggest entry comes first");
+
+    // SEARCH IN DIRECTORY
+    {
+        app.process_events(&mut terminal, into_keys(b"/z1".iter()))?;
+        assert_eq!(
+            selected_name(&app),
+            "z123.b",
+            "it selects the first match while typing"
+        );
+
+        app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Esc)))?;
+        assert!(app.state.search.is_none(), "escape cancels the search");
+        assert_eq!(
+            selected_name(&app),
+            "dir",
+            "it returns to where it was before the search"
+        );
+
+        app.process_events(&mut terminal, into_keys(b"/\t*.*\n".iter()))?;
+        assert_eq!(
+            selected_name(&app),
+            ".hidden.666",
+            "tab switches to glob patterns"
+        );
+        app.process_events(&mut terminal, into_keys(b"nn".iter()))?;
+        assert_eq!(selected_name(&app), "c.lnk", "n moves to the next matches");
+        app.process_events(&mut terminal, into_keys(b"N".iter()))?;
+        assert_eq!(
+            selected_name(&app),
+            "z123.b",
+            "N moves to the previous match"
+        );
+
+        app.process_events(&mut terminal, into_keys(b"q".iter()))?;
+        assert!(
+            app.state.search.is_none(),
+            "quitting clears the search instead of exiting"
+        );
+        app.process_events(&mut terminal, into_keys(b"n".iter()))?;
+        assert_eq!(
+            selected_name(&app),
+            "z123.b",
+            "without search, n does nothing"
+        );
+    }
+
+    // SEARCH IN TREE
+    {
+        app.process_events(&mut terminal, into_keys(b"fsub-a\n".iter()))?;
+        assert_eq!(
+            selected_name(&app),
+            "dir-sub-a.256kb",
+            "it selects matches anywhere in the tree"
+        );
+        assert_eq!(
+            node_by_index(&app, app.state.root).name.to_string_lossy(),
+            "sub",
+            "it shows the directory containing the match"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sorting_by_name_count_mtime_and_extension() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let entry_names = |app: &crate::interactive::TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| e.data.name.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+    };
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+
+    app.process_events(&mut terminal, into_keys(b"S".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::NameAscending);
+    assert_eq!(
+        entry_names(&app),
+        vec![".hidden.666", "a", "b.empty", "c.lnk", "dir", "z123.b"],
+        "S sorts by name next"
+    );
+    app.process_events(&mut terminal, into_keys(b"R".iter()))?;
+    assert_eq!(
+        entry_names(&app),
+        vec!["z123.b", "dir", "c.lnk", "b.empty", "a", ".hidden.666"],
+        "R reverses the order"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"S".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::CountDescending);
+    assert_eq!(
+        node_by_index(&app, app.state.entries[0].index).entry_count,
+        7,
+        "the directory with the most entries comes first"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"SS".iter()))?;
+    assert_eq!(app.state.sorting, SortMode::ExtensionAscending);
+    assert_eq!(
+        entry_names(&app),
+        vec!["a", "dir", ".hidden.666", "z123.b", "b.empty", "c.lnk"],
+        "entries without extension come first, and ties are ordered by name"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"s".iter()))?;
+    assert_eq!(
+        app.state.sorting,
+        SortMode::SizeDescending,
+        "s switches back to sorting by size"
+    );
+    Ok(())
+}
+
+#[test]
+fn expanding_and_collapsing_directories_in_the_tree_view() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let selected_name = |app: &crate::interactive::TerminalApp| {
+        node_by_index(app, app.state.selected.expect("a selection"))
+            .name
+            .to_string_lossy()
+            .into_owned()
+    };
+    app.process_events(&mut terminal, into_keys(b"ot".iter()))?;
+    assert!(app.state.is_tree_view, "t switches to the tree view");
+    assert_eq!(app.state.entries.len(), 6, "nothing is expanded initially");
+    assert_eq!(selected_name(&app), "dir");
+
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(
+        app.state.entries.len(),
+        11,
+        "descending expands the directory in place"
+    );
+    assert_eq!(selected_name(&app), "dir", "the selection stays");
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(
+        selected_name(&app),
+        "dir-a.1mb",
+        "descending into an expanded directory selects its first entry"
+    );
+    assert_eq!(
+        app.state.entries[1].depth, 1,
+        "entries of expanded directories are nested"
+    );
+
+    app.process_events(&mut terminal, into_keys(b" ".iter()))?;
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(1),
+        "nested entries can be marked"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(selected_name(&app), "dir", "ascending selects the parent");
+    app.process_events(&mut terminal, into_keys(b"u".iter()))?;
+    assert_eq!(app.state.entries.len(), 6, "ascending again collapses it");
+
+    app.process_events(&mut terminal, into_keys(b"ooot".iter()))?;
+    assert!(!app.state.is_tree_view);
+    assert_eq!(
+        selected_name(&app),
+        "dir",
+        "a nested selection moves to its ancestor in the list view"
+    );
+    Ok(())
+}
+
+#[test]
+fn columns_show_the_parent_and_the_selected_directory() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(160, 20))?;
+    let screen = |terminal: &tui_react::Terminal<tui::backend::TestBackend>| {
+        terminal
+            .backend
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect::<String>()
+    };
+
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert!(
+        !screen(&terminal).contains("dir-a.1mb"),
+        "entries of the selected directory aren't shown by default"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"C".iter()))?;
+    assert!(app.display.show_columns);
+    let screen = screen(&terminal);
+    assert!(
+        screen.contains("dir-a.1mb"),
+        "the selected directory is previewed"
+    );
+    assert_eq!(
+        screen.matches("sample-01").count(),
+        2,
+        "the parent directory is shown as well, besides the title of the current one"
+    );
+    Ok(())
+}
+
+#[test]
+fn treemap_navigates_between_rectangles_and_descends() -> Result<()> {
+    use crate::interactive::app::FocussedPane;
+
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let mut terminal = tui_react::Terminal::new(tui::backend::TestBackend::new(160, 40))?;
+    let top_level_root = app.state.root;
+
+    app.process_events(&mut terminal, into_keys(b"T".iter()))?;
+    assert!(app.window.treemap_pane.is_some(), "the treemap is open");
+    assert_eq!(app.state.focussed, FocussedPane::Treemap, "and focussed");
+
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        fixture_str("sample-01"),
+        "entering a directory changes the root of the treemap as well"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"o".iter()))?;
+    let first = app.state.selected;
+    app.process_events(&mut terminal, into_keys(b"l".iter()))?;
+    let right = app.state.selected;
+    assert_ne!(right, first, "moving right selects the next rectangle");
+    app.process_events(&mut terminal, into_keys(b"h".iter()))?;
+    assert_eq!(
+        app.state.selected, first,
+        "moving left goes back to where we were"
+    );
+    app.process_events(&mut terminal, into_keys(b"jk".iter()))?;
+    assert!(app.state.selected.is_some());
+
+    app.process_events(&mut terminal, into_keys(b"uu".iter()))?;
+    assert_eq!(app.state.root, top_level_root, "exiting goes back up");
+
+    app.process_events(&mut terminal, into_keys(b"q".iter()))?;
+    assert!(
+        app.window.treemap_pane.is_none(),
+        "quitting closes the treemap"
+    );
+    assert_eq!(app.state.focussed, FocussedPane::Main);
+    Ok(())
+}
+
+#[test]
+fn named_bookmarks_and_history_jump_between_directories() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let top_level_root = app.state.root;
+
+    app.process_events(&mut terminal, into_keys(b"oojo".iter()))?;
+    let sub = app.state.root;
+    assert_eq!(node_by_index(&app, sub).name, OsString::from("sub"));
+    let bookmarked = app.state.selected;
+    app.process_events(&mut terminal, into_keys(b"ma".iter()))?;
+
+    app.process_events(&mut terminal, into_keys(b"uuu".iter()))?;
+    assert_eq!(app.state.root, top_level_root);
+    app.process_events(&mut terminal, into_keys(b"'z".iter()))?;
+    assert_eq!(
+        app.state.root, top_level_root,
+        "unknown bookmarks go nowhere"
+    );
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("There is no bookmark 'z'")
+    );
+
+    app.process_events(&mut terminal, into_keys(b"'a".iter()))?;
+    assert_eq!(
+        app.state.root, sub,
+        "jumping goes to the directory of the bookmark"
+    );
+    assert_eq!(
+        app.state.selected, bookmarked,
+        "and selects the bookmarked entry"
+    );
+
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Ctrl('o'))))?;
+    assert_eq!(
+        app.state.root, top_level_root,
+        "going back returns to where we jumped from"
+    );
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Ctrl('o'))))?;
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        fixture_str("sample-01"),
+        "and further back to the directories visited before"
+    );
+    app.process_events(&mut terminal, (0..2).map(|_| Event::Key(Key::Ctrl('n'))))?;
+    assert_eq!(app.state.root, sub, "going forward retraces the steps");
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Ctrl('n'))))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("There is no directory to go forward to")
+    );
+    Ok(())
+}
+
+#[test]
+fn go_to_path_resolves_relative_and_absolute_paths_with_completion() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    app.process_events(&mut terminal, into_keys(b"cdi\t".iter()))?;
+    assert_eq!(
+        app.state.go_to.as_ref().map(|g| g.text.as_str()),
+        Some("dir/"),
+        "paths are relative to the single scanned path, and directories are completed with a slash"
+    );
+    app.process_events(&mut terminal, into_keys(b"dir-a\t".iter()))?;
+    let go_to = app.state.go_to.as_ref().expect("still editing");
+    assert_eq!(
+        go_to.text, "dir/dir-a.",
+        "ambiguous completions extend the common prefix"
+    );
+    assert_eq!(go_to.status.as_deref(), Some("dir-a.1mb  dir-a.kb"));
+
+    app.process_events(&mut terminal, into_keys(b"kb\n".iter()))?;
+    assert!(app.state.go_to.is_none());
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        OsString::from("dir"),
+        "files are shown in their directory"
+    );
+    assert_eq!(
+        app.state
+            .selected
+            .map(|idx| node_by_index(&app, idx).name.as_os_str().to_owned()),
+        Some(OsString::from("dir-a.kb"))
+    );
+
+    app.process_events(&mut terminal, into_keys(b"c".iter()))?;
+    assert_eq!(
+        app.state.go_to.as_ref().map(|g| g.text.clone()),
+        Some(format!("{}/", fixture_str("sample-01/dir"))),
+        "editing starts at the current directory"
+    );
+    app.process_events(&mut terminal, into_keys(b"nope\n".iter()))?;
+    let go_to = app
+        .state
+        .go_to
+        .as_ref()
+        .expect("errors keep the prompt open");
+    assert!(go_to.is_error);
+    assert_eq!(go_to.status.as_deref(), Some("There is no entry 'nope'"));
+
+    let absolute = std::env::current_dir()?.join(fixture_str("sample-01/dir/sub"));
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Ctrl('u'))))?;
+    app.process_events(
+        &mut terminal,
+        into_keys(absolute.to_str().unwrap().as_bytes().iter().chain(b"\n")),
+    )?;
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        OsString::from("sub"),
+        "directories are entered"
+    );
+    Ok(())
+}
+
+#[test]
+fn command_line_sorts_filters_goes_to_paths_and_rescans() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    app.process_events(&mut terminal, into_keys(b":sort name desc\n".iter()))?;
+    assert!(!app.state.command_line.is_editing);
+    assert_eq!(app.state.sorting, SortMode::NameDescending);
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":cd dir\n:filter >500KB\n".iter()),
+    )?;
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        OsString::from("dir")
+    );
+    let names = |app: &TerminalApp| {
+        app.state
+            .entries
+            .iter()
+            .map(|e| e.data.name.as_os_str().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(&app),
+        vec![OsString::from("dir-a.1mb")],
+        "only entries larger than the filter are shown"
+    );
+
+    app.process_events(&mut terminal, into_keys(b":bogus\n".iter()))?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Unknown command 'bogus'")
+    );
+    app.process_events(&mut terminal, into_keys(b":".iter()))?;
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Up)))?;
+    assert_eq!(
+        app.state.command_line.text, "bogus",
+        "previous command lines can be recalled"
+    );
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Esc)))?;
+    assert!(!app.state.command_line.is_editing);
+
+    app.process_events(&mut terminal, into_keys(b":filter\n:rescan\n".iter()))?;
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        OsString::from("dir"),
+        "the current directory is shown again after rescanning"
+    );
+    assert_eq!(app.state.message.as_deref(), Some("Rescanned"));
+    assert_eq!(names(&app).len(), 5);
+    Ok(())
+}
+
+#[test]
+fn rules_mark_matching_files_in_the_whole_tree_once_confirmed() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+
+    app.process_events(&mut terminal, into_keys(b":mark-tree *kb >100KB\n".iter()))?;
+    let message = app.state.message.clone().unwrap_or_default();
+    assert!(
+        message.starts_with("Mark 1 file with ")
+            && message.ends_with(" matching '*kb >100.00 KB'? Press 'y' to mark them"),
+        "{}",
+        message
+    );
+    app.process_events(&mut terminal, into_keys(b"n".iter()))?;
+    assert_eq!(app.state.message.as_deref(), Some("Nothing was marked"));
+    assert!(app.window.mark_pane.is_none());
+
+    app.process_events(&mut terminal, into_keys(b":mark-tree *kb >100KB\ny".iter()))?;
+    assert_eq!(app.state.message.as_deref(), Some("Marked 1 file"));
+    let paths: Vec<_> = app
+        .window
+        .mark_pane
+        .as_ref()
+        .expect("files were marked")
+        .marked()
+        .values()
+        .map(|m| m.path.clone())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![std::path::PathBuf::from(fixture_str(
+            "sample-01/dir/sub/dir-sub-a.256kb"
+        ))],
+        "files are found in all directories below the current one"
+    );
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":mark-tree older 100000d\n".iter()),
+    )?;
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("No files match 'older 100000d'")
+    );
+    assert!(app.state.marks_preview.is_none());
+    Ok(())
+}
+
+#[test]
+fn ranges_are_marked_and_marks_are_inverted_or_removed_below_a_directory() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let marked_names = |app: &TerminalApp| {
+        let mut names: Vec<_> = app
+            .window
+            .mark_pane
+            .as_ref()
+            .map(|pane| {
+                pane.marked()
+                    .keys()
+                    .map(|idx| node_by_index(app, *idx).name.to_string_lossy().into_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    };
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":cd dir\n:sort name\nHVjj".iter()),
+    )?;
+    assert_eq!(app.state.visual_range(), Some(0..=2));
+    app.process_events(&mut terminal, into_keys(b"V".iter()))?;
+    assert_eq!(app.state.message.as_deref(), Some("Marked 3 entries"));
+    assert_eq!(
+        marked_names(&app),
+        vec!["1000bytes", "dir-a.1mb", "dir-a.kb"]
+    );
+
+    app.process_events(&mut terminal, into_keys(b"a".iter()))?;
+    assert_eq!(
+        marked_names(&app),
+        vec!["empty-dir", "sub"],
+        "marks are inverted"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"M".iter()))?;
+    assert_eq!(marked_names(&app).len(), 5, "all entries are marked");
+    app.process_events(&mut terminal, into_keys(b"M".iter()))?;
+    assert!(
+        app.window.mark_pane.is_none(),
+        "all entries are unmarked if all were marked"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"Vj".iter()))?;
+    app.process_events(&mut terminal, std::iter::once(Event::Key(Key::Esc)))?;
+    assert!(app.state.visual_anchor.is_none(), "<Esc> cancels the range");
+    assert!(app.window.mark_pane.is_none());
+
+    app.process_events(&mut terminal, into_keys(b"HxGox".iter()))?;
+    assert_eq!(
+        node_by_index(&app, app.state.root).name,
+        OsString::from("sub")
+    );
+    assert_eq!(marked_names(&app), vec!["1000bytes", "dir-sub-a.256kb"]);
+    app.process_events(&mut terminal, into_keys(b"U".iter()))?;
+    assert_eq!(
+        marked_names(&app),
+        vec!["1000bytes"],
+        "only entries below the current directory are unmarked"
+    );
+    app.process_events(&mut terminal, into_keys(b"uU".iter()))?;
+    assert!(app.window.mark_pane.is_none());
+    Ok(())
+}
@@ -1,16 +1,22 @@
 use crate::interactive::app::tests::utils::{
-    initialized_app_and_terminal_from_paths, into_keys, WritableFixture,
+    index_by_name, initialized_app_and_terminal_from_paths, into_keys, WritableFixture,
 };
 use anyhow::Result;
 use crosstermion::input::Event;
 use crosstermion::input::Key;
+use dua::traverse::SizeKind;
 use pretty_assertions::assert_eq;
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+};
 
 #[test]
 #[cfg(not(target_os = "windows"))] // it stopped working here, don't know if it's truly broken or if it's the test. Let's wait for windows users to report.
 fn basic_user_journey_with_deletion() -> Result<()> {
     let fixture = WritableFixture::from("sample-02");
-    let (mut terminal, mut app) = initialized_app_and_terminal_from_paths(&[fixture.root.clone()])?;
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
 
     // With a selection of items
     app.process_events(&mut terminal, into_keys(b"doddd".iter()))?;
@@ -28,6 +34,7 @@ fn basic_user_journey_with_deletion() -> Result<()> {
         &mut terminal,
         vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
     )?;
+    app.wait_for_deletion();
     assert!(
         app.window.mark_pane.is_none(),
         "the marker pane is gone as all entries have been removed"
@@ -43,3 +50,487 @@ fn basic_user_journey_with_deletion() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn marks_are_exported_and_imported_with_missing_entries_reported() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let list = fixture.root.join("marks.txt");
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":cd dir\n:sort name\nHjxGx:export ".iter()),
+    )?;
+    app.process_events(
+        &mut terminal,
+        into_keys(list.to_str().unwrap().as_bytes().iter().chain(b"\n")),
+    )?;
+    let exported = std::fs::read_to_string(&list)?;
+    let lines: Vec<_> = exported.lines().collect();
+    assert_eq!(lines.len(), 3, "a header and one line per marked entry");
+    assert_eq!(lines[0], "# size on disk\tapparent size\tpath");
+    assert!(
+        lines[1].ends_with(&format!(
+            "\t1000000\t{}",
+            fixture.root.join("dir/dir-a.1mb").display()
+        )),
+        "{}",
+        lines[1]
+    );
+
+    std::fs::remove_file(fixture.root.join("dir/dir-a.1mb"))?;
+    app.process_events(&mut terminal, into_keys(b":rescan\n".iter()))?;
+    assert_eq!(
+        app.state.message,
+        Some(format!(
+            "Rescanned and marked 1 of 2 entries, 1 no longer exist: {}",
+            fixture.root.join("dir/dir-a.1mb").display()
+        )),
+        "marks are found again after rescanning"
+    );
+    let marked_paths = |app: &crate::interactive::TerminalApp| -> Vec<_> {
+        app.window
+            .mark_pane
+            .as_ref()
+            .expect("the existing entry is marked")
+            .marked()
+            .values()
+            .map(|mark| (mark.path.clone(), mark.is_dir))
+            .collect()
+    };
+    assert_eq!(
+        marked_paths(&app),
+        vec![(fixture.root.join("dir/sub"), true)]
+    );
+
+    app.process_events(&mut terminal, into_keys(b"\ta:import ".iter()))?;
+    assert!(app.window.mark_pane.is_none(), "all marks were removed");
+    app.process_events(
+        &mut terminal,
+        into_keys(list.to_str().unwrap().as_bytes().iter().chain(b"\n")),
+    )?;
+    assert_eq!(
+        app.state.message,
+        Some(format!(
+            "Marked 1 of 2 entries, 1 no longer exist: {}",
+            fixture.root.join("dir/dir-a.1mb").display()
+        ))
+    );
+    assert_eq!(
+        marked_paths(&app),
+        vec![(fixture.root.join("dir/sub"), true)]
+    );
+    Ok(())
+}
+
+#[test]
+fn marks_with_any_names_are_exported_and_imported_intact() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let odd = fixture.root.join("odd");
+    std::fs::create_dir(&odd)?;
+    let mut names = vec![
+        std::ffi::OsString::from("tab\tname"),
+        "new\nline\r".into(),
+        r"back\slash\x41".into(),
+    ];
+    #[cfg(unix)]
+    names.push(std::os::unix::ffi::OsStringExt::from_vec(
+        b"not-utf8-\xff".to_vec(),
+    ));
+    for name in &names {
+        std::fs::write(odd.join(name), b"content")?;
+    }
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let list = fixture.root.join("marks.txt");
+    let marked_paths = |app: &crate::interactive::TerminalApp| -> BTreeSet<_> {
+        app.window
+            .mark_pane
+            .as_ref()
+            .map(|pane| {
+                pane.marked()
+                    .values()
+                    .map(|mark| mark.path.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    app.process_events(&mut terminal, into_keys(b":cd odd\nH".iter()))?;
+    app.process_events(&mut terminal, into_keys(names.iter().flat_map(|_| b"x")))?;
+    let expected: BTreeSet<_> = names.iter().map(|name| odd.join(name)).collect();
+    assert_eq!(marked_paths(&app), expected);
+
+    let export_and_import = format!(":export {0}\n\ta:import {0}\n", list.to_str().unwrap());
+    app.process_events(
+        &mut terminal,
+        into_keys(export_and_import.as_bytes().iter()),
+    )?;
+    assert_eq!(
+        app.state.message,
+        Some(format!("Marked {} entries", names.len()))
+    );
+    assert_eq!(
+        marked_paths(&app),
+        expected,
+        "tabs, newlines, backslashes and bytes that aren't UTF-8 survive the round-trip"
+    );
+    Ok(())
+}
+
+#[test]
+fn empty_directories_are_entered_when_going_to_them() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    std::fs::create_dir(fixture.root.join("dir").join("nothing"))?;
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(&mut terminal, into_keys(b"cdir/no\t".iter()))?;
+    assert_eq!(
+        app.state.go_to.as_ref().map(|g| g.text.as_str()),
+        Some("dir/nothing/"),
+        "empty directories are completed as directories"
+    );
+    app.process_events(&mut terminal, into_keys(b"\n".iter()))?;
+    assert_eq!(app.state.root, index_by_name(&app, "nothing"));
+    assert!(app.state.entries.is_empty());
+    Ok(())
+}
+
+#[test]
+fn nested_marks_are_counted_and_deleted_once() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":cd dir\n:sort name\nGxoxuu:mark dir\n".iter()),
+    )?;
+    let dir = index_by_name(&app, "dir");
+    let pane = app.window.mark_pane.as_ref().expect("entries are marked");
+    assert_eq!(pane.marked().len(), 3);
+    assert_eq!(
+        pane.covered().len(),
+        2,
+        "sub and the file in it are covered by dir"
+    );
+    assert_eq!(
+        pane.size(SizeKind::Apparent),
+        app.traversal.tree[dir].apparent_size,
+        "only the sizes of entries that aren't covered are summed up"
+    );
+
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    app.wait_for_deletion();
+    assert!(
+        app.window.mark_pane.is_none(),
+        "all entries were deleted without errors"
+    );
+    assert!(!fixture.root.join("dir").exists());
+    assert_eq!(
+        app.traversal.tree[app.traversal.root_index].entry_count as usize,
+        app.traversal.tree.node_count() - 1,
+        "deleted entries are no longer counted by their ancestors"
+    );
+    Ok(())
+}
+
+#[test]
+fn covered_marks_are_kept_if_the_deletion_is_not_confirmed() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.confirm_deletion = true;
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":cd dir\n:sort name\nGxoxuu:mark dir\n".iter()),
+    )?;
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    let request = app
+        .state
+        .deletion_request
+        .as_ref()
+        .expect("deleting asks for confirmation first");
+    assert_eq!(
+        request.summary.paths,
+        vec![fixture.root.join("dir")],
+        "covered entries are deleted with their directory"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"n".iter()))?;
+    let pane = app.window.mark_pane.as_ref().expect("entries are marked");
+    assert_eq!(pane.marked().len(), 3, "all marks are kept");
+    assert_eq!(pane.covered().len(), 2, "and still covered by dir");
+    assert!(fixture.root.join("dir").exists());
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn hard_linked_files_are_only_freed_if_all_links_are_marked() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    std::fs::hard_link(
+        fixture.root.join("dir/sub/dir-sub-a.256kb"),
+        fixture.root.join("z-link"),
+    )?;
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    assert_eq!(app.traversal.hard_links.links.len(), 2);
+
+    app.process_events(
+        &mut terminal,
+        into_keys(b":mark-tree dir-sub-a.256kb\ny".iter()),
+    )?;
+    let pane = app.window.mark_pane.as_ref().expect("file is marked");
+    assert_eq!(
+        pane.freed(SizeKind::Apparent),
+        0,
+        "the other link keeps the file alive"
+    );
+
+    app.process_events(&mut terminal, into_keys(b":mark-tree z-link\ny".iter()))?;
+    let pane = app.window.mark_pane.as_ref().expect("files are marked");
+    assert_eq!(
+        pane.freed(SizeKind::Apparent),
+        256_000,
+        "the file is freed once with all of its links"
+    );
+    assert_eq!(
+        pane.size(SizeKind::Apparent),
+        256_000,
+        "only one of the links was counted during the traversal"
+    );
+
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    app.wait_for_deletion();
+    assert!(app.window.mark_pane.is_none());
+    assert!(
+        app.traversal.hard_links.links.is_empty() && app.traversal.hard_links.inodes.is_empty(),
+        "deleted links are forgotten"
+    );
+    Ok(())
+}
+
+#[test]
+fn deletion_is_summarized_and_only_done_once_confirmed() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    app.state.confirm_deletion = true;
+
+    app.process_events(&mut terminal, into_keys(b"o:mark dir\n:mark a\n".iter()))?;
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    let request = app
+        .state
+        .deletion_request
+        .as_ref()
+        .expect("deleting asks for confirmation first");
+    assert_eq!(
+        (request.summary.files, request.summary.directories),
+        (5, 3),
+        "entries below marked directories are counted, including empty directories"
+    );
+    assert_eq!(
+        request.summary.paths,
+        vec![fixture.root.join("dir"), fixture.root.join("a")],
+        "paths are listed in the order of the mark pane"
+    );
+    let screen: String = terminal
+        .backend
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol.as_str())
+        .collect();
+    assert!(screen.contains("Confirm deletion"), "{}", screen);
+
+    app.process_events(&mut terminal, into_keys(b"n".iter()))?;
+    assert!(app.state.deletion_request.is_none());
+    assert_eq!(app.state.message.as_deref(), Some("Nothing was deleted"));
+    assert!(fixture.root.join("dir").exists() && fixture.root.join("a").exists());
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(2),
+        "marks are kept when the deletion is cancelled"
+    );
+
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Ctrl('r')), Event::Key(Key::Char('y'))].into_iter(),
+    )?;
+    app.wait_for_deletion();
+    assert!(app.window.mark_pane.is_none());
+    assert!(!fixture.root.join("dir").exists() && !fixture.root.join("a").exists());
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn dry_runs_write_a_script_deleting_the_marked_entries() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+    let script = fixture.root.join("deletion.sh");
+    app.state.confirm_deletion = true;
+    app.state.dry_run = Some(script.clone());
+
+    app.process_events(&mut terminal, into_keys(b"o:mark dir\n:mark a\n".iter()))?;
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    assert!(
+        app.state.deletion_request.is_none(),
+        "dry runs don't need to be confirmed"
+    );
+    assert_eq!(
+        app.window.mark_pane.as_ref().map(|p| p.marked().len()),
+        Some(2),
+        "nothing was deleted"
+    );
+    assert!(fixture.root.join("dir").exists() && fixture.root.join("a").exists());
+
+    let content = std::fs::read_to_string(&script)?;
+    assert!(content.starts_with("#!/bin/sh\n"), "{}", content);
+    assert!(
+        content.contains(&format!(
+            "\nrm -rf -- '{}'\n",
+            fixture.root.join("dir").display()
+        )),
+        "{}",
+        content
+    );
+
+    app.process_events(&mut terminal, into_keys(b"w".iter()))?;
+    assert_eq!(
+        app.state.message,
+        Some(format!(
+            "'{}' exists already and was left as it is, remove it to write a new script",
+            script.display()
+        ))
+    );
+    assert_eq!(std::fs::read_to_string(&script)?, content);
+
+    #[cfg(feature = "trash-move")]
+    {
+        std::fs::remove_file(&script)?;
+        app.process_events(&mut terminal, vec![Event::Key(Key::Ctrl('t'))].into_iter())?;
+        std::fs::remove_file(&script)?;
+        app.process_events(&mut terminal, into_keys(b"w".iter()))?;
+        let content = std::fs::read_to_string(&script)?;
+        assert!(
+            content.contains(&format!(
+                "\ngio trash -- '{}'\n",
+                fixture.root.join("dir").display()
+            )),
+            "scripts trash entries once that was asked for last: {}",
+            content
+        );
+        std::fs::remove_file(&script)?;
+        app.process_events(&mut terminal, vec![Event::Key(Key::Ctrl('r'))].into_iter())?;
+    }
+
+    let status = std::process::Command::new("sh").arg(&script).status()?;
+    assert!(status.success());
+    assert!(!fixture.root.join("dir").exists() && !fixture.root.join("a").exists());
+    Ok(())
+}
+
+#[test]
+fn deletion_runs_in_the_background_and_can_be_cancelled() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(&mut terminal, into_keys(b"o:mark dir\n:mark a\n".iter()))?;
+    let marked: Vec<_> = app
+        .window
+        .mark_pane
+        .as_ref()
+        .expect("entries are marked")
+        .marked()
+        .iter()
+        .map(|(index, mark)| (*index, mark.path.clone()))
+        .collect();
+    let gate = Arc::new(Mutex::new(()));
+    let held_gate = gate.lock().expect("not poisoned");
+    app.state.deletion_gate = Some(gate.clone());
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    assert!(app.state.deletion.is_some(), "deletion happens on a worker");
+    let message = app.state.message.clone().unwrap_or_default();
+    assert!(
+        message.starts_with("Deleting entry 1 of 2, "),
+        "the progress is shown: {}",
+        message
+    );
+
+    app.process_events(&mut terminal, vec![Event::Key(Key::Esc)].into_iter())?;
+    drop(held_gate);
+    app.wait_for_deletion();
+    assert!(app.state.deletion.is_none());
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("Cancelled after 0 of 2 entries were deleted")
+    );
+    for (index, path) in marked {
+        let is_marked = app
+            .window
+            .mark_pane
+            .as_ref()
+            .is_some_and(|pane| pane.marked().contains_key(&index));
+        assert!(
+            path.exists() && app.traversal.tree.contains_node(index) && is_marked,
+            "entries that weren't deleted remain marked: {}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn closing_the_help_pane_keeps_deleting() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let (mut terminal, mut app) =
+        initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+
+    app.process_events(&mut terminal, into_keys(b"o:mark dir\n:mark a\n".iter()))?;
+    let gate = Arc::new(Mutex::new(()));
+    let held_gate = gate.lock().expect("not poisoned");
+    app.state.deletion_gate = Some(gate.clone());
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('\t')), Event::Key(Key::Ctrl('r'))].into_iter(),
+    )?;
+    app.process_events(
+        &mut terminal,
+        vec![Event::Key(Key::Char('?')), Event::Key(Key::Esc)].into_iter(),
+    )?;
+    assert!(app.window.help_pane.is_none(), "the help pane is closed");
+
+    drop(held_gate);
+    app.wait_for_deletion();
+    assert_eq!(
+        app.state.message.as_deref(),
+        Some("All 2 entries were deleted"),
+        "and the deletion wasn't cancelled"
+    );
+    Ok(())
+}
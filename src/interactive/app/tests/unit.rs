@@ -1,7 +1,16 @@
-use crate::interactive::app::tests::utils::{
-    debug, initialized_app_and_terminal_from_fixture, sample_01_tree, sample_02_tree,
+use crate::interactive::{
+    app::tests::utils::{
+        debug, fixture, index_by_name, initialized_app_and_terminal_from_fixture,
+        initialized_app_and_terminal_from_paths, into_keys, node_by_index, sample_01_tree,
+        sample_02_tree, without_volatile_data, WritableFixture,
+    },
+    delete_directory_recursively, Columns, DeletionProgress, Sizes,
 };
 use anyhow::Result;
+use dua::{
+    traverse::{SizeKind, Traversal},
+    ByteFormat, SortMode, TraversalSorting, WalkOptions,
+};
 use pretty_assertions::assert_eq;
 
 #[test]
@@ -10,7 +19,7 @@ fn it_can_handle_ending_traversal_reaching_top_but_skipping_levels() -> Result<(
     let expected_tree = sample_01_tree();
 
     assert_eq!(
-        debug(app.traversal.tree),
+        debug(without_volatile_data(app.traversal.tree)),
         debug(expected_tree),
         "filesystem graph is stable and matches the directory structure"
     );
@@ -23,9 +32,301 @@ fn it_can_handle_ending_traversal_without_reaching_the_top() -> Result<()> {
     let expected_tree = sample_02_tree();
 
     assert_eq!(
-        debug(app.traversal.tree),
+        debug(without_volatile_data(app.traversal.tree)),
         debug(expected_tree),
         "filesystem graph is stable and matches the directory structure"
     );
     Ok(())
 }
+
+#[test]
+fn it_records_both_sizes_and_can_toggle_between_them() -> Result<()> {
+    let (mut terminal, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    assert_eq!(
+        app.state.size_kind,
+        SizeKind::Apparent,
+        "the initial size kind is derived from the walk options"
+    );
+
+    let file = node_by_index(&app, index_by_name(&app, "dir-a.1mb"));
+    assert_eq!(file.apparent_size, 1_000_000);
+    assert!(
+        file.size_on_disk > 0,
+        "the size on disk is recorded in the same traversal"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"A".iter()))?;
+    assert_eq!(
+        app.state.size_kind,
+        SizeKind::OnDisk,
+        "'A' toggles the size kind"
+    );
+    assert_eq!(
+        app.traversal.total_bytes(SizeKind::OnDisk),
+        Some(node_by_index(&app, app.traversal.root_index).size_on_disk),
+        "totals follow the size kind"
+    );
+
+    app.process_events(&mut terminal, into_keys(b"A".iter()))?;
+    assert_eq!(app.state.size_kind, SizeKind::Apparent, "and toggles back");
+    Ok(())
+}
+
+#[test]
+fn it_counts_the_size_of_directories_themselves_if_configured() -> Result<()> {
+    let traversal = Traversal::from_walk(
+        WalkOptions {
+            threads: 1,
+            byte_format: ByteFormat::Metric,
+            apparent_size: true,
+            count_hard_links: false,
+            count_directory_sizes: true,
+            sorting: TraversalSorting::AlphabeticalByFileName,
+            cross_filesystems: false,
+            ignore_dirs: Vec::new(),
+        },
+        vec![fixture("sample-02")],
+        |_| Ok(false),
+    )?
+    .expect("traversal to complete");
+
+    let dir_size = |p: &str| fixture(p).symlink_metadata().map(|m| m.len() as u128);
+    let own_sizes = dir_size("sample-02")?
+        + dir_size("sample-02/dir")?
+        + dir_size("sample-02/dir/empty-dir")?
+        + dir_size("sample-02/dir/sub")?;
+    assert_eq!(
+        traversal.total_bytes(SizeKind::Apparent),
+        Some(1540 + own_sizes),
+        "each directory adds its own size on top of the one of its files"
+    );
+
+    let sub = traversal
+        .tree
+        .node_indices()
+        .find(|idx| traversal.tree[*idx].name.as_os_str() == "sub")
+        .expect("sub directory is present");
+    assert_eq!(
+        traversal.tree[sub].apparent_size,
+        1024 + dir_size("sample-02/dir/sub")?,
+        "directory sizes include their own size"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn recursive_deletion_never_follows_symlinks() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let dir = fixture.root.join("dir");
+    std::os::unix::fs::symlink(fixture.root.join("a"), dir.join("file.lnk"))?;
+    std::os::unix::fs::symlink(&fixture.root, dir.join("sub").join("root.lnk"))?;
+
+    let progress = DeletionProgress::new(None);
+    let dir_link = fixture.root.join("dir.lnk");
+    std::os::unix::fs::symlink(&dir, &dir_link)?;
+    assert_eq!(
+        delete_directory_recursively(
+            dir_link.clone(),
+            &fixture.root,
+            &Sizes::default(),
+            &progress
+        ),
+        Ok(())
+    );
+    assert!(
+        dir_link.symlink_metadata().is_err() && dir.join("dir-a.1mb").is_file(),
+        "only the symlink to the directory is deleted"
+    );
+
+    let progress = DeletionProgress::new(None);
+    assert_eq!(
+        delete_directory_recursively(dir.clone(), &fixture.root, &Sizes::default(), &progress),
+        Ok(())
+    );
+    assert!(!dir.exists());
+    assert!(
+        fixture.root.join("a").is_file() && fixture.root.join("z123.b").is_file(),
+        "the targets of symlinks are kept"
+    );
+    assert_eq!(
+        progress.files_removed(),
+        9,
+        "4 files, 2 symlinks and 3 directories were removed"
+    );
+
+    assert_eq!(
+        delete_directory_recursively(dir, &fixture.root, &Sizes::default(), &progress),
+        Ok(()),
+        "entries that don't exist count as deleted"
+    );
+    Ok(())
+}
+
+#[test]
+fn columns_are_listed_again_only_once_what_they_show_changed() -> Result<()> {
+    let (_, mut app) = initialized_app_and_terminal_from_fixture(&["sample-01"])?;
+    let dir = index_by_name(&app, "dir");
+    let sub = index_by_name(&app, "sub");
+    let file = index_by_name(&app, "dir-sub-a.256kb");
+    let preview = |columns: &Columns| -> Option<Vec<_>> {
+        columns.preview.as_ref().map(|(index, entries)| {
+            assert_eq!(*index, sub);
+            entries.iter().map(|e| e.data.name.clone()).collect()
+        })
+    };
+
+    let mut columns = Columns::default();
+    let refresh = |columns: &mut Columns, app: &crate::interactive::TerminalApp, sorting| {
+        columns.refresh(&app.traversal, dir, Some(sub), sorting, SizeKind::Apparent)
+    };
+    refresh(&mut columns, &app, SortMode::SizeDescending);
+    assert_eq!(
+        columns.parent.as_ref().map(|(_, entries)| entries.len()),
+        Some(6),
+        "the parent of the current directory is listed"
+    );
+    assert_eq!(preview(&columns), Some(vec!["dir-sub-a.256kb".into()]));
+
+    app.traversal.tree[file].name = "renamed".into();
+    refresh(&mut columns, &app, SortMode::SizeDescending);
+    assert_eq!(
+        preview(&columns),
+        Some(vec!["dir-sub-a.256kb".into()]),
+        "nothing is listed again while nothing changed that it depends on"
+    );
+    refresh(&mut columns, &app, SortMode::NameAscending);
+    assert_eq!(preview(&columns), Some(vec!["renamed".into()]));
+
+    app.traversal.tree.remove_node(file);
+    refresh(&mut columns, &app, SortMode::NameAscending);
+    assert_eq!(
+        preview(&columns),
+        None,
+        "changes to the tree are picked up, and directories without entries aren't previewed"
+    );
+    Ok(())
+}
+
+#[test]
+fn removed_bytes_are_counted_per_entry_with_the_size_kind_in_use() -> Result<()> {
+    for kind in [SizeKind::Apparent, SizeKind::OnDisk] {
+        let fixture = WritableFixture::from("sample-01");
+        let (_, app) =
+            initialized_app_and_terminal_from_paths(std::slice::from_ref(&fixture.root))?;
+        let dir = index_by_name(&app, "dir");
+
+        let progress = DeletionProgress::new(None);
+        assert_eq!(
+            delete_directory_recursively(
+                fixture.root.join("dir"),
+                &fixture.root,
+                &Sizes::of(&app.traversal.tree, dir, kind),
+                &progress
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            u128::from(progress.bytes_removed()),
+            app.traversal.tree[dir].size(kind),
+            "each removed entry adds its own bytes"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn recursive_deletion_never_follows_symlinks_replacing_scanned_directories() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let dir = fixture.root.join("dir");
+    let elsewhere = fixture.root.join("elsewhere");
+    std::fs::create_dir_all(elsewhere.join("sub"))?;
+    std::fs::write(elsewhere.join("sub").join("dir-sub-a.256kb"), b"kept")?;
+    std::fs::rename(&dir, fixture.root.join("dir.moved"))?;
+    std::os::unix::fs::symlink(&elsewhere, &dir)?;
+
+    let progress = DeletionProgress::new(None);
+    assert_eq!(
+        delete_directory_recursively(dir.join("sub"), &fixture.root, &Sizes::default(), &progress),
+        Err(1),
+        "the directory that was scanned is gone"
+    );
+    assert!(elsewhere.join("sub").join("dir-sub-a.256kb").is_file());
+    assert_eq!(progress.files_removed(), 0);
+    Ok(())
+}
+
+#[test]
+fn wide_directory_trees_are_deleted() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let top = fixture.root.join("wide");
+    for a in 0..8 {
+        for b in 0..8 {
+            let dir = top.join(a.to_string()).join(b.to_string());
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(dir.join("file"), b"content")?;
+        }
+    }
+
+    let progress = DeletionProgress::new(None);
+    assert_eq!(
+        delete_directory_recursively(top.clone(), &fixture.root, &Sizes::default(), &progress),
+        Ok(())
+    );
+    assert!(top.symlink_metadata().is_err());
+    assert_eq!(progress.files_removed(), 1 + 8 + 64 + 64);
+    Ok(())
+}
+
+#[test]
+fn deeply_nested_directories_are_deleted() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let top = fixture.root.join("deep");
+    let mut deepest = top.clone();
+    for _ in 0..1000 {
+        deepest.push("d");
+    }
+    std::fs::create_dir_all(&deepest)?;
+    std::fs::write(deepest.join("file"), b"content")?;
+
+    let progress = DeletionProgress::new(None);
+    assert_eq!(
+        delete_directory_recursively(top.clone(), &fixture.root, &Sizes::default(), &progress),
+        Ok(())
+    );
+    assert!(top.symlink_metadata().is_err());
+    assert_eq!(progress.files_removed(), 1002);
+    Ok(())
+}
+
+#[test]
+fn cancelled_deletion_fails_only_if_the_entry_remains() -> Result<()> {
+    let fixture = WritableFixture::from("sample-01");
+    let progress = DeletionProgress::new(None);
+    progress.cancel();
+
+    assert_eq!(
+        delete_directory_recursively(
+            fixture.root.join("a"),
+            &fixture.root,
+            &Sizes::default(),
+            &progress
+        ),
+        Ok(()),
+        "a file is removed before the cancellation is noticed"
+    );
+    assert!(fixture.root.join("a").symlink_metadata().is_err());
+    assert!(
+        delete_directory_recursively(
+            fixture.root.join("dir"),
+            &fixture.root,
+            &Sizes::default(),
+            &progress
+        )
+        .is_err(),
+        "the directory isn't removed as its entries were skipped"
+    );
+    assert!(fixture.root.join("dir").join("dir-a.1mb").is_file());
+    Ok(())
+}
@@ -13,11 +13,12 @@ use std::{
     fs::{copy, create_dir_all, remove_dir, remove_file},
     io::ErrorKind,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 use tui::backend::TestBackend;
 use tui_react::Terminal;
 
-use crate::interactive::{app::tests::FIXTURE_PATH, Interaction, TerminalApp};
+use crate::interactive::{app::tests::FIXTURE_PATH, AppOptions, Interaction, TerminalApp};
 
 pub fn into_keys<'a>(
     bytes: impl Iterator<Item = &'a u8> + 'a,
@@ -49,7 +50,7 @@ pub fn index_by_name_and_size(
         .node_indices()
         .map(|idx| (idx, node_by_index(app, idx)))
         .filter_map(|(idx, e)| {
-            if e.name == name && size.map(|s| s == e.size).unwrap_or(true) {
+            if e.name == name && size.map(|s| s == e.apparent_size).unwrap_or(true) {
                 Some(idx)
             } else {
                 None
@@ -69,11 +70,13 @@ pub fn index_by_name(app: &TerminalApp, name: impl AsRef<OsStr>) -> TreeIndex {
 
 pub struct WritableFixture {
     pub root: PathBuf,
+    /// The directory only this fixture is copied into, so tests can run in parallel.
+    dir: PathBuf,
 }
 
 impl Drop for WritableFixture {
     fn drop(&mut self) {
-        delete_recursive(&self.root).ok();
+        delete_recursive(&self.dir).ok();
     }
 }
 
@@ -101,9 +104,7 @@ fn delete_recursive(path: impl AsRef<Path>) -> Result<()> {
                 .sorted_by_key(|p| p.components().count())
                 .rev()
                 .map(|d| {
-                    remove_dir(d)
-                        .with_context(|| format!("Could not delete '{}'", d.display()))
-                        .map_err(Error::from)
+                    remove_dir(d).with_context(|| format!("Could not delete '{}'", d.display()))
                 }),
         )
         .collect::<Result<_, _>>()
@@ -140,14 +141,19 @@ fn copy_recursive(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<(), Er
 impl From<&'static str> for WritableFixture {
     fn from(fixture_name: &str) -> Self {
         const TEMP_TLD_DIRNAME: &str = "dua-unit";
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
 
         let src = fixture(fixture_name);
-        let dst = temp_dir().join(TEMP_TLD_DIRNAME);
-        create_dir_all(&dst).unwrap();
+        let dir = temp_dir().join(TEMP_TLD_DIRNAME).join(format!(
+            "{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        create_dir_all(&dir).unwrap();
 
-        let dst = dst.join(fixture_name);
+        let dst = dir.join(fixture_name);
         copy_recursive(src, &dst).unwrap();
-        WritableFixture { root: dst }
+        WritableFixture { root: dst, dir }
     }
 }
 
@@ -180,10 +186,12 @@ pub fn initialized_app_and_terminal_with_closure(
             byte_format: ByteFormat::Metric,
             apparent_size: true,
             count_hard_links: false,
+            count_directory_sizes: false,
             sorting: TraversalSorting::AlphabeticalByFileName,
             cross_filesystems: false,
             ignore_dirs: Vec::new(),
         },
+        AppOptions::default(),
         input_paths,
         Interaction::None,
     )?
@@ -252,7 +260,7 @@ pub fn sample_01_tree() -> Tree {
             }
         }
     }
-    tree
+    with_entry_counts(tree)
 }
 
 pub fn sample_02_tree() -> Tree {
@@ -286,15 +294,15 @@ pub fn sample_02_tree() -> Tree {
             }
         }
     }
-    tree
+    with_entry_counts(tree)
 }
 
 pub fn make_add_node(t: &mut Tree) -> impl FnMut(&str, u128, Option<NodeIndex>) -> NodeIndex + '_ {
     move |name, size, maybe_from_idx| {
         let n = t.add_node(EntryData {
             name: PathBuf::from(name),
-            size,
-            metadata_io_error: false,
+            apparent_size: size,
+            ..Default::default()
         });
         if let Some(from) = maybe_from_idx {
             t.add_edge(from, n, ());
@@ -303,6 +311,35 @@ pub fn make_add_node(t: &mut Tree) -> impl FnMut(&str, u128, Option<NodeIndex>)
     }
 }
 
+/// Clear the size on disk and the modification time, which depend on the filesystem and checkout,
+/// to allow comparing trees anywhere.
+pub fn without_volatile_data(mut tree: Tree) -> Tree {
+    for entry in tree.node_weights_mut() {
+        entry.size_on_disk = 0;
+        entry.mtime = None;
+    }
+    tree
+}
+
+/// Set the amount of entries contained in each directory of `tree`, and which entries are directories,
+/// as the traversal would. All directories in the fixtures contain entries, and the root isn't a directory.
+fn with_entry_counts(mut tree: Tree) -> Tree {
+    for idx in tree.node_indices().collect::<Vec<_>>() {
+        let mut bfs = petgraph::visit::Bfs::new(&tree, idx);
+        let mut count = 0;
+        while bfs.next(&tree).is_some() {
+            count += 1;
+        }
+        tree[idx].entry_count = count - 1;
+        tree[idx].is_dir = count > 1
+            && tree
+                .neighbors_directed(idx, petgraph::Direction::Incoming)
+                .next()
+                .is_some();
+    }
+    tree
+}
+
 pub fn debug(item: impl fmt::Debug) -> String {
     format!("{:?}", item)
 }
@@ -0,0 +1,114 @@
+use crate::interactive::widgets::{MarkMode, Theme};
+use dua::ByteFormat;
+use std::{borrow::Borrow, path::PathBuf};
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+/// What deleting all marked entries affects, to show it before doing so.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeletionSummary {
+    pub files: u64,
+    pub directories: u64,
+    /// The total size of all marked entries.
+    pub bytes: u128,
+    /// The amount of bytes deleting the marked entries actually frees.
+    pub freed: u128,
+    /// The paths of all marked entries which aren't within another marked entry.
+    pub paths: Vec<PathBuf>,
+}
+
+/// A dialog asking to confirm the deletion of marked entries, shown on top of everything else.
+pub struct ConfirmDeletion;
+
+pub struct ConfirmDeletionProps<'a> {
+    pub mode: &'a MarkMode,
+    pub summary: &'a DeletionSummary,
+    pub format: ByteFormat,
+    pub theme: &'a Theme,
+}
+
+impl ConfirmDeletion {
+    pub fn render<'a>(
+        &self,
+        props: impl Borrow<ConfirmDeletionProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let ConfirmDeletionProps {
+            mode,
+            summary,
+            format,
+            theme,
+        } = props.borrow();
+
+        let (verb, action) = match mode {
+            MarkMode::Delete => ("Permanently delete", "delete them"),
+            #[cfg(feature = "trash-move")]
+            MarkMode::Trash => ("Move to the trash", "trash them"),
+        };
+        let plural =
+            |n: u64, one: &str, many: &str| format!("{} {}", n, if n == 1 { one } else { many });
+        let mut lines = vec![
+            Spans::from(Span::styled(
+                format!(
+                    "{} {} and {} with {} ({} actually freed)?",
+                    verb,
+                    plural(summary.files, "file", "files"),
+                    plural(summary.directories, "directory", "directories"),
+                    format.display(summary.bytes),
+                    format.display(summary.freed),
+                ),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Spans::default(),
+        ];
+
+        let width = area.width.saturating_sub(4).min(100);
+        // Leave room for the borders, the question, the answer, the blank lines between them and a margin.
+        let max_paths = (area.height.saturating_sub(8) as usize).max(1);
+        let num_shown = if summary.paths.len() > max_paths {
+            max_paths - 1
+        } else {
+            summary.paths.len()
+        };
+        lines.extend(
+            summary
+                .paths
+                .iter()
+                .take(num_shown)
+                .map(|path| Spans::from(format!("  {}", path.display()))),
+        );
+        if num_shown < summary.paths.len() {
+            lines.push(Spans::from(Span::styled(
+                format!("  …and {} more", summary.paths.len() - num_shown),
+                Style::default().add_modifier(Modifier::ITALIC),
+            )));
+        }
+        lines.push(Spans::default());
+        lines.push(Spans::from(Span::styled(
+            format!("Press 'y' to {}, or any other key to cancel", action),
+            Style::default().fg(theme.message),
+        )));
+
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let dialog = Rect {
+            x: area.x + area.width.saturating_sub(width) / 2,
+            y: area.y + area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        };
+        Clear.render(dialog, buf);
+        Paragraph::new(Text::from(lines))
+            .block(
+                Block::default()
+                    .title(" Confirm deletion ")
+                    .borders(Borders::ALL),
+            )
+            .render(dialog, buf);
+    }
+}
@@ -1,15 +1,15 @@
 use crate::interactive::{
     path_of,
-    widgets::{entry_color, EntryMarkMap},
-    DisplayOptions, EntryDataBundle,
+    widgets::{EntryMarkMap, Theme},
+    Action, DisplayOptions, EntryDataBundle, KeyBindings, Scope, Search, SizeFilter, SortMode,
 };
-use dua::traverse::{Tree, TreeIndex};
+use dua::traverse::{EntryData, SizeKind, Tree, TreeIndex};
 use itertools::Itertools;
-use std::{borrow::Borrow, path::Path};
+use std::{borrow::Borrow, collections::BTreeSet, ops::RangeInclusive, path::Path};
 use tui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Span,
     widgets::{Block, Borders},
 };
@@ -26,9 +26,21 @@ pub struct EntriesProps<'a> {
     pub display: DisplayOptions,
     pub selected: Option<TreeIndex>,
     pub entries: &'a [EntryDataBundle],
+    pub size_kind: SizeKind,
+    pub sorting: SortMode,
     pub marked: Option<&'a EntryMarkMap>,
     pub border_style: Style,
     pub is_focussed: bool,
+    pub bindings: &'a KeyBindings,
+    pub theme: &'a Theme,
+    /// If set, entries matching the search are highlighted.
+    pub search: Option<&'a Search>,
+    /// If set, entries are shown as a tree with these directories expanded.
+    pub expanded: Option<&'a BTreeSet<TreeIndex>>,
+    /// If set, only entries of matching size are shown.
+    pub filter: Option<SizeFilter>,
+    /// If set, the entries at these positions are highlighted as range about to be marked.
+    pub range: Option<RangeInclusive<usize>>,
 }
 
 #[derive(Default)]
@@ -48,10 +60,18 @@ impl Entries {
             root,
             display,
             entries,
+            size_kind,
+            sorting,
             selected,
             marked,
             border_style,
             is_focussed,
+            bindings,
+            theme,
+            search,
+            expanded,
+            filter,
+            range,
         } = props.borrow();
         let list = &mut self.list;
 
@@ -61,7 +81,20 @@ impl Entries {
                 .is_none()
         };
 
-        let total: u128 = entries.iter().map(|b| b.data.size).sum();
+        let total: u128 = entries
+            .iter()
+            .filter(|b| b.depth == 0)
+            .map(|b| b.data.size(*size_kind))
+            .sum();
+        // nested entries of the tree view are shown relative to their parent
+        let total_of = |node_idx, depth| match depth {
+            0 => total,
+            _ => tree
+                .neighbors_directed(node_idx, petgraph::Incoming)
+                .next()
+                .and_then(|parent| tree.node_weight(parent))
+                .map_or(total, |parent| parent.size(*size_kind)),
+        };
         let title = match path_of(tree, *root).to_string_lossy().to_string() {
             ref p if p.is_empty() => Path::new(".")
                 .canonicalize()
@@ -70,13 +103,17 @@ impl Entries {
             p => p,
         };
         let title = format!(
-            " {} ({} item{}) ",
+            " {} ({} item{}, by {}{}) ",
             title,
             entries.len(),
             match entries.len() {
                 1 => "",
                 _ => "s",
-            }
+            },
+            sorting,
+            filter
+                .map(|filter| format!(", only {filter}"))
+                .unwrap_or_default()
         );
         let block = Block::default()
             .title(title.as_str())
@@ -94,20 +131,28 @@ impl Entries {
             block: Some(block),
             entry_in_view,
         };
-        let lines = entries.iter().map(
-            |EntryDataBundle {
-                 index: node_idx,
-                 data: w,
-                 is_dir,
-                 exists,
-             }| {
+        let lines = entries.iter().enumerate().map(
+            |(
+                position,
+                EntryDataBundle {
+                    index: node_idx,
+                    data: w,
+                    is_dir,
+                    exists,
+                    metadata,
+                    depth,
+                },
+            )| {
                 let mut style = Style::default();
                 let is_selected = if let Some(idx) = selected {
                     *idx == *node_idx
                 } else {
                     false
                 };
-                if is_selected {
+                let is_in_range = range
+                    .as_ref()
+                    .is_some_and(|range| range.contains(&position));
+                if is_selected || is_in_range {
                     style.add_modifier.insert(Modifier::REVERSED);
                 }
                 if *is_focussed & is_selected {
@@ -117,15 +162,24 @@ impl Entries {
                 let bytes = Span::styled(
                     format!(
                         "{:>byte_column_width$}",
-                        display.byte_format.display(w.size).to_string(), // we would have to impl alignment/padding ourselves otherwise...
+                        display.byte_format.display(w.size(*size_kind)).to_string(), // we would have to impl alignment/padding ourselves otherwise...
                         byte_column_width = display.byte_format.width()
                     ),
                     Style {
-                        fg: Color::Green.into(),
+                        fg: theme.size.into(),
                         ..style
                     },
                 );
-                let fraction = w.size as f32 / total as f32;
+                let ratio = display.show_size_ratio.then(|| {
+                    Span::styled(
+                        format!(" {:>6}", size_ratio(w)),
+                        Style {
+                            fg: theme.size.into(),
+                            ..style
+                        },
+                    )
+                });
+                let fraction = w.size(*size_kind) as f32 / total_of(*node_idx, *depth) as f32;
                 let should_avoid_showing_a_big_reversed_bar = fraction > 0.9;
                 let local_style = if should_avoid_showing_a_big_reversed_bar {
                     style.remove_modifier(Modifier::REVERSED)
@@ -143,31 +197,79 @@ impl Entries {
                 let name = Span::styled(
                     fill_background_to_right(
                         format!(
-                            "{prefix}{}",
+                            "{indent}{prefix}{}",
                             w.name.to_string_lossy(),
-                            prefix = if *is_dir && !is_top(*root) { "/" } else { " " }
+                            indent = "  ".repeat(*depth),
+                            prefix = match expanded {
+                                Some(expanded) if *is_dir => {
+                                    if expanded.contains(node_idx) {
+                                        "▾"
+                                    } else {
+                                        "▸"
+                                    }
+                                }
+                                _ if *is_dir && !is_top(*root) => "/",
+                                _ => " ",
+                            }
                         ),
                         area.width,
                     ),
                     {
                         let is_marked = marked.map(|m| m.contains_key(node_idx)).unwrap_or(false);
-                        let fg = if !exists {
-                            // non-existing - always red!
-                            Some(Color::Red)
+                        if !exists {
+                            Style {
+                                fg: theme.missing.into(),
+                                ..style
+                            }
+                        } else if let Some(ls_style) = (!is_marked)
+                            .then(|| {
+                                theme.ls_style(&w.name, metadata.as_ref(), || {
+                                    path_of(tree, *node_idx)
+                                })
+                            })
+                            .flatten()
+                        {
+                            style.patch(ls_style)
                         } else {
-                            entry_color(style.fg, !*is_dir, is_marked)
-                        };
-                        Style { fg, ..style }
+                            let style = Style {
+                                fg: theme.entry_color(style.fg, !*is_dir, is_marked),
+                                ..style
+                            };
+                            if is_marked {
+                                style.add_modifier(theme.marked_modifier)
+                            } else {
+                                style
+                            }
+                        }
+                        .patch(match search {
+                            Some(search) if search.is_match(&w.name) => Style {
+                                fg: (!is_marked).then_some(theme.search_match),
+                                add_modifier: Modifier::BOLD | Modifier::UNDERLINED,
+                                ..Style::default()
+                            },
+                            _ => Style::default(),
+                        })
                     },
                 );
-                vec![bytes, left_bar, percentage, right_bar, name]
+                vec![
+                    Some(bytes),
+                    ratio,
+                    Some(left_bar),
+                    Some(percentage),
+                    Some(right_bar),
+                    Some(name),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
             },
         );
 
         list.render(props, lines, area, buf);
 
         if *is_focussed {
-            let help_text = " . = o|.. = u ── ⇊ = Ctrl+d|↓ = j|⇈ = Ctrl+u|↑ = k ";
+            let help_text = bindings.navigation_hint(Scope::Main, true);
+            let help_text = help_text.as_str();
             let help_text_block_width = block_width(help_text);
             let bound = Rect {
                 width: area.width.saturating_sub(1),
@@ -182,7 +284,15 @@ impl Entries {
                 );
             }
             let bound = line_bound(bound, bound.height.saturating_sub(1) as usize);
-            let help_text = " mark-move = d | mark-toggle = space | toggle-all = a";
+            let help_text = bindings.hint(
+                Scope::Main,
+                &[
+                    ("mark-move", Action::ToggleMarkAndAdvance),
+                    ("mark-toggle", Action::ToggleMark),
+                    ("toggle-all", Action::ToggleAllMarks),
+                ],
+            );
+            let help_text = help_text.as_str();
             let help_text_block_width = block_width(help_text);
             if help_text_block_width <= bound.width {
                 draw_text_nowrap_fn(
@@ -195,3 +305,15 @@ impl Entries {
         }
     }
 }
+
+/// Display how much space `entry` takes on disk relative to its apparent size.
+/// Sparse and compressed files have a ratio below 1.
+fn size_ratio(entry: &EntryData) -> String {
+    match entry.apparent_size {
+        0 => "-".into(),
+        apparent => match entry.size_on_disk as f64 / apparent as f64 {
+            ratio if ratio >= 1000.0 => ">999x".into(),
+            ratio => format!("{ratio:.2}x"),
+        },
+    }
+}
@@ -1,4 +1,5 @@
-use dua::ByteFormat;
+use crate::interactive::widgets::Theme;
+use dua::{traverse::SizeKind, ByteFormat};
 use std::borrow::Borrow;
 use tui::{
     buffer::Buffer,
@@ -11,29 +12,37 @@ use tui::{
 
 pub struct Footer;
 
-pub struct FooterProps {
+pub struct FooterProps<'a> {
     pub total_bytes: Option<u128>,
+    pub size_kind: SizeKind,
     pub entries_traversed: u64,
     pub traversal_start: std::time::Instant,
     pub elapsed: Option<std::time::Duration>,
     pub format: ByteFormat,
     pub message: Option<String>,
+    pub theme: &'a Theme,
 }
 
 impl Footer {
-    pub fn render(&self, props: impl Borrow<FooterProps>, area: Rect, buf: &mut Buffer) {
+    pub fn render<'a>(&self, props: impl Borrow<FooterProps<'a>>, area: Rect, buf: &mut Buffer) {
         let FooterProps {
             total_bytes,
+            size_kind,
             entries_traversed,
             elapsed,
             traversal_start,
             format,
             message,
+            theme,
         } = props.borrow();
 
         let spans = vec![
             Span::from(format!(
-                " Total disk usage: {}  Entries: {} {progress}  ",
+                " Total {}: {}  Entries: {} {progress}  ",
+                match size_kind {
+                    SizeKind::OnDisk => "disk usage",
+                    SizeKind::Apparent => "apparent size",
+                },
                 match total_bytes {
                     Some(b) => format!("{}", format.display(*b)),
                     None => "-".to_owned(),
@@ -56,7 +65,7 @@ impl Footer {
                 Span::styled(
                     m,
                     Style {
-                        fg: Color::Red.into(),
+                        fg: theme.message.into(),
                         bg: Color::Reset.into(),
                         add_modifier: Modifier::BOLD | Modifier::RAPID_BLINK,
                         ..Style::default()
@@ -67,7 +76,7 @@ impl Footer {
         Paragraph::new(Text::from(Spans::from(
             spans.into_iter().flatten().collect::<Vec<_>>(),
         )))
-        .style(Style::default().add_modifier(Modifier::REVERSED))
+        .style(theme.footer_style())
         .render(area, buf);
     }
 }
@@ -9,9 +9,9 @@ use tui::{
 pub struct Header;
 
 impl Header {
-    pub fn render(&self, bg_color: Color, area: Rect, buf: &mut Buffer) {
+    pub fn render(&self, fg_color: Color, bg_color: Color, area: Rect, buf: &mut Buffer) {
         let standard = Style {
-            fg: Color::Black.into(),
+            fg: fg_color.into(),
             bg: bg_color.into(),
             ..Default::default()
         };
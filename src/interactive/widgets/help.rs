@@ -1,10 +1,9 @@
-use crate::interactive::CursorDirection;
-use crosstermion::{input::Key, input::Key::*};
+use crate::interactive::{widgets::Theme, Action, CursorDirection, KeyBindings};
 use std::{borrow::Borrow, cell::RefCell};
 use tui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
     widgets::{Block, Borders, Paragraph, Widget},
 };
@@ -18,9 +17,28 @@ pub struct HelpPane {
     pub scroll: u16,
 }
 
-pub struct HelpPaneProps {
+pub struct HelpPaneProps<'a> {
     pub border_style: Style,
     pub has_focus: bool,
+    pub bindings: &'a KeyBindings,
+    pub theme: &'a Theme,
+}
+
+const KEYS_COLUMN_WIDTH: usize = 11;
+
+/// Join `keys` with `/` into as few groups as possible that each fit into `width`.
+fn group_keys(keys: Vec<String>, width: usize) -> Vec<String> {
+    let mut groups = Vec::<String>::new();
+    for key in keys {
+        match groups.last_mut() {
+            Some(group) if group.len() + 1 + key.len() <= width => {
+                group.push('/');
+                group.push_str(&key);
+            }
+            _ => groups.push(key),
+        }
+    }
+    groups
 }
 
 fn margin(r: Rect, margin: u16) -> Rect {
@@ -33,14 +51,14 @@ fn margin(r: Rect, margin: u16) -> Rect {
 }
 
 impl HelpPane {
-    pub fn process_events(&mut self, key: Key) {
-        match key {
-            Char('H') => self.scroll_help(CursorDirection::ToTop),
-            Char('G') => self.scroll_help(CursorDirection::ToBottom),
-            Ctrl('u') | PageUp => self.scroll_help(CursorDirection::PageUp),
-            Char('k') | Up => self.scroll_help(CursorDirection::Up),
-            Char('j') | Down => self.scroll_help(CursorDirection::Down),
-            Ctrl('d') | PageDown => self.scroll_help(CursorDirection::PageDown),
+    pub fn process_events(&mut self, action: Action) {
+        match action {
+            Action::ToTop => self.scroll_help(CursorDirection::ToTop),
+            Action::ToBottom => self.scroll_help(CursorDirection::ToBottom),
+            Action::PageUp => self.scroll_help(CursorDirection::PageUp),
+            Action::Up => self.scroll_help(CursorDirection::Up),
+            Action::Down => self.scroll_help(CursorDirection::Down),
+            Action::PageDown => self.scroll_help(CursorDirection::PageDown),
             _ => {}
         };
     }
@@ -48,7 +66,19 @@ impl HelpPane {
         self.scroll = direction.move_cursor(self.scroll as usize) as u16;
     }
 
-    pub fn render(&mut self, props: impl Borrow<HelpPaneProps>, area: Rect, buf: &mut Buffer) {
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<HelpPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let HelpPaneProps {
+            border_style,
+            has_focus,
+            bindings,
+            theme,
+        } = props.borrow();
+
         let lines = {
             let lines = RefCell::new(Vec::<Spans>::with_capacity(30));
             let add_newlines = |n| {
@@ -68,9 +98,9 @@ impl HelpPane {
                 )));
                 add_newlines(1);
             };
-            let hotkey = |keys, description, other_line: Option<&str>| {
+            let hotkey = |keys: String, description, other_line: Option<&str>| {
                 let separator_size = 3;
-                let column_size = 11 + separator_size;
+                let column_size = KEYS_COLUMN_WIDTH + separator_size;
                 lines.borrow_mut().push(Spans::from(vec![
                     Span::styled(
                         format!(
@@ -79,7 +109,7 @@ impl HelpPane {
                             column_size = column_size - separator_size
                         ),
                         Style {
-                            fg: Color::Green.into(),
+                            fg: theme.help_key.into(),
                             ..Default::default()
                         },
                     ),
@@ -95,110 +125,25 @@ impl HelpPane {
                 }
             };
 
-            title("Keys for pane control");
-            {
-                hotkey(
-                    "q/<Esc>",
-                    "Close the current pane. Closes the program if no",
-                    Some("pane is open"),
-                );
-                hotkey(
-                    "<Tab>",
-                    "Cycle between all open panes.",
-                    Some("Activate 'Marked Items' pane to delete selected files."),
-                );
-                hotkey("?", "Show or hide the help pane", None);
-                spacer();
-            }
-            title("Keys for Navigation");
-            {
-                hotkey("j/<Down>", "move down an entry", None);
-                hotkey("k/<Up>", "move up an entry", None);
-                hotkey("o/l/<Enter>", "descent into the selected directory", None);
-                hotkey("<Right>", "^", None);
-                hotkey(
-                    "u/h/<Left>",
-                    "ascent one level into the parent directory",
-                    None,
-                );
-                hotkey("<Backspace>", "^", None);
-                hotkey("Ctrl + d", "move down 10 entries at once", None);
-                hotkey("<Page Down>", "^", None);
-                hotkey("Ctrl + u", "move up 10 entries at once", None);
-                hotkey("<Page Up>", "^", None);
-                hotkey("H/<Home>", "Move to the top of the entries list", None);
-                hotkey("G/<End>", "Move to the bottom of the entries list", None);
-                spacer();
-            }
-            title("Keys for display");
-            {
-                hotkey("s", "toggle sort by size ascending/descending", None);
-                hotkey(
-                    "g",
-                    "cycle through percentage display and bar options",
-                    None,
-                );
-                spacer();
-            }
-            title("Keys for entry operations");
-            {
-                hotkey(
-                    "Shift + o",
-                    "Open the entry with the associated program",
-                    None,
-                );
-                hotkey(
-                    "d",
-                    "Toggle the currently selected entry and move down",
-                    None,
-                );
-                hotkey(
-                    "x",
-                    "Mark the currently selected entry for deletion and move down",
-                    None,
-                );
-                hotkey("<Space>", "Toggle the currently selected entry", None);
-                hotkey("a", "Toggle all entries", None);
-                spacer();
-            }
-            title("Keys in the Mark pane");
-            {
-                hotkey(
-                    "x/d/<Space>",
-                    "Remove the selected entry from the list",
-                    None,
-                );
-                hotkey("a", "Remove all entries from the list", None);
-                hotkey(
-                    "Ctrl + r",
-                    "Permanently delete all marked entries without prompt!",
-                    Some("This operation cannot be undone!"),
-                );
-                #[cfg(feature = "trash-move")]
-                hotkey(
-                    "Ctrl + t",
-                    "Move all marked entries to the trash bin",
-                    Some("The entries can be restored from the trash bin"),
-                );
-                spacer();
-            }
-            title("Keys for application control");
-            {
-                hotkey(
-                    "Ctrl + c",
-                    "close the application. No questions asked!",
-                    None,
-                );
+            for (section, actions) in bindings.help() {
+                title(section);
+                for (keys, action) in actions {
+                    let (description, second_line) = action.description();
+                    let mut keys = group_keys(keys, KEYS_COLUMN_WIDTH).into_iter();
+                    hotkey(
+                        keys.next().unwrap_or_else(|| "-".into()),
+                        description,
+                        second_line,
+                    );
+                    for keys in keys {
+                        hotkey(keys, "^", None);
+                    }
+                }
                 spacer();
             }
             lines.into_inner()
         };
 
-        let HelpPaneProps {
-            border_style,
-            has_focus,
-        } = props.borrow();
-
         let title = "Help";
         let block = Block::default()
             .title(title)
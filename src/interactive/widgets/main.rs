@@ -1,7 +1,8 @@
 use crate::interactive::{
     widgets::{
-        Entries, EntriesProps, Footer, FooterProps, Header, HelpPane, HelpPaneProps, MarkPane,
-        MarkPaneProps, COLOR_MARKED,
+        ConfirmDeletion, ConfirmDeletionProps, Entries, EntriesProps, Footer, FooterProps, Header,
+        HelpPane, HelpPaneProps, MarkPane, MarkPaneProps, Prompt, PromptProps, Treemap,
+        TreemapProps,
     },
     AppState, DisplayOptions, FocussedPane,
 };
@@ -12,6 +13,7 @@ use tui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::Modifier,
     style::{Color, Style},
+    widgets::{Block, Borders, Widget},
 };
 use Constraint::*;
 use FocussedPane::*;
@@ -26,7 +28,12 @@ pub struct MainWindowProps<'a> {
 pub struct MainWindow {
     pub help_pane: Option<HelpPane>,
     pub entries_pane: Entries,
+    /// The entries of the parent directory, shown in columns.
+    pub parent_pane: Entries,
+    /// The entries of the selected directory, shown in columns.
+    pub preview_pane: Entries,
     pub mark_pane: Option<MarkPane>,
+    pub treemap_pane: Option<Treemap>,
 }
 
 impl MainWindow {
@@ -38,10 +45,9 @@ impl MainWindow {
     ) {
         let MainWindowProps {
             traversal:
-                Traversal {
+                traversal @ Traversal {
                     tree,
                     entries_traversed,
-                    total_bytes,
                     start,
                     elapsed,
                     ..
@@ -50,18 +56,19 @@ impl MainWindow {
             state,
         } = props.borrow();
 
-        let (entries_style, help_style, mark_style) = {
-            let grey = Style {
-                fg: Color::DarkGray.into(),
-                bg: Color::Reset.into(),
-                add_modifier: Modifier::empty(),
-                ..Style::default()
-            };
+        let grey = Style {
+            fg: state.theme.inactive_border.into(),
+            bg: Color::Reset.into(),
+            add_modifier: Modifier::empty(),
+            ..Style::default()
+        };
+        let (entries_style, help_style, mark_style, treemap_style) = {
             let bold = Style::default().add_modifier(Modifier::BOLD);
             match state.focussed {
-                Main => (bold, grey, grey),
-                Help => (grey, bold, grey),
-                Mark => (grey, grey, bold),
+                Main => (bold, grey, grey, grey),
+                Help => (grey, bold, grey, grey),
+                Mark => (grey, grey, bold, grey),
+                FocussedPane::Treemap => (grey, grey, grey, bold),
             }
         };
 
@@ -72,14 +79,15 @@ impl MainWindow {
                 .split(area);
             (regions[0], regions[1], regions[2])
         };
+        let body_area = entries_area;
         {
             let marked = self.mark_pane.as_ref().map(|p| p.marked());
-            let bg_color = match (marked.map_or(true, |m| m.is_empty()), state.focussed) {
-                (false, FocussedPane::Mark) => Color::LightRed,
-                (false, _) => COLOR_MARKED,
-                (_, _) => Color::White,
+            let bg_color = match (marked.into_iter().all(|m| m.is_empty()), state.focussed) {
+                (false, FocussedPane::Mark) => state.theme.danger,
+                (false, _) => state.theme.marked,
+                (_, _) => state.theme.header_bg,
             };
-            Header.render(bg_color, header_area, buf);
+            Header.render(state.theme.header_fg, bg_color, header_area, buf);
         }
         let (entries_area, help_pane, mark_pane) = {
             let regions = Layout::default()
@@ -109,6 +117,9 @@ impl MainWindow {
             let props = MarkPaneProps {
                 border_style: mark_style,
                 format: display.byte_format,
+                size_kind: state.size_kind,
+                bindings: &state.bindings,
+                theme: &state.theme,
             };
             pane.render(props, mark_area, buf);
         }
@@ -117,31 +128,183 @@ impl MainWindow {
             let props = HelpPaneProps {
                 border_style: help_style,
                 has_focus: matches!(state.focussed, Help),
+                bindings: &state.bindings,
+                theme: &state.theme,
             };
             pane.render(props, help_area, buf);
         }
 
+        let entries_area = match self.treemap_pane.as_mut() {
+            Some(pane) => {
+                let regions = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Percentage(50), Percentage(50)].as_ref())
+                    .split(entries_area);
+                let props = TreemapProps {
+                    tree,
+                    root: state.root,
+                    selected: state.selected,
+                    size_kind: state.size_kind,
+                    border_style: treemap_style,
+                    theme: &state.theme,
+                };
+                pane.render(props, regions[1], buf);
+                regions[0]
+            }
+            None => entries_area,
+        };
+
         let marked = self.mark_pane.as_ref().map(|p| p.marked());
+        let entries_area = if display.show_columns {
+            let parent = state.columns.parent.as_ref();
+            let regions = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(if parent.is_some() {
+                    [Percentage(25), Percentage(40), Percentage(35)].as_ref()
+                } else {
+                    [Percentage(55), Percentage(45)].as_ref()
+                })
+                .split(entries_area);
+            let (parent_area, entries_area, preview_area) = match parent {
+                Some(_) => (Some(regions[0]), regions[1], regions[2]),
+                None => (None, regions[0], regions[1]),
+            };
+            let column_props = |root, selected, entries| EntriesProps {
+                tree,
+                root,
+                display: *display,
+                entries,
+                size_kind: state.size_kind,
+                sorting: state.sorting,
+                marked,
+                selected,
+                border_style: grey,
+                is_focussed: false,
+                bindings: &state.bindings,
+                theme: &state.theme,
+                search: None,
+                expanded: None,
+                filter: None,
+                range: None,
+            };
+            if let (Some((parent, entries)), Some(parent_area)) = (parent, parent_area) {
+                self.parent_pane.render(
+                    column_props(*parent, Some(state.root), entries),
+                    parent_area,
+                    buf,
+                );
+            }
+            match &state.columns.preview {
+                Some((selected, entries)) => {
+                    let preview_selected = state.bookmarks.get(selected).copied();
+                    self.preview_pane.render(
+                        column_props(*selected, preview_selected, entries),
+                        preview_area,
+                        buf,
+                    );
+                }
+                None => Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(grey)
+                    .render(preview_area, buf),
+            }
+            entries_area
+        } else {
+            entries_area
+        };
         let props = EntriesProps {
             tree,
             root: state.root,
             display: *display,
             entries: &state.entries,
+            size_kind: state.size_kind,
+            sorting: state.sorting,
             marked,
             selected: state.selected,
             border_style: entries_style,
             is_focussed: matches!(state.focussed, Main),
+            bindings: &state.bindings,
+            theme: &state.theme,
+            search: state.search.as_ref(),
+            expanded: state.is_tree_view.then_some(&state.expanded),
+            filter: state.filter,
+            range: state.visual_range(),
         };
         self.entries_pane.render(props, entries_area, buf);
 
+        if let Some(request) = state.deletion_request.as_ref() {
+            ConfirmDeletion.render(
+                ConfirmDeletionProps {
+                    mode: &request.mode,
+                    summary: &request.summary,
+                    format: display.byte_format,
+                    theme: &state.theme,
+                },
+                body_area,
+                buf,
+            );
+        }
+
+        if let Some(search) = state.search.as_ref().filter(|s| s.is_editing) {
+            let (status, is_error) = match &search.error {
+                Some(err) => (err.clone(), true),
+                None if search.text.is_empty() => (String::new(), false),
+                None => match search.num_matches(&state.entries) {
+                    1 => ("1 match".into(), false),
+                    n => (format!("{n} matches"), false),
+                },
+            };
+            Prompt.render(
+                PromptProps {
+                    title: search.title(),
+                    input: &search.text,
+                    status: Some(status),
+                    is_error,
+                    theme: &state.theme,
+                },
+                footer_area,
+                buf,
+            );
+            return;
+        }
+        if state.command_line.is_editing {
+            Prompt.render(
+                PromptProps {
+                    title: "Command".into(),
+                    input: &state.command_line.text,
+                    status: None,
+                    is_error: false,
+                    theme: &state.theme,
+                },
+                footer_area,
+                buf,
+            );
+            return;
+        }
+        if let Some(go_to) = state.go_to.as_ref() {
+            Prompt.render(
+                PromptProps {
+                    title: "Go to".into(),
+                    input: &go_to.text,
+                    status: go_to.status.clone(),
+                    is_error: go_to.is_error,
+                    theme: &state.theme,
+                },
+                footer_area,
+                buf,
+            );
+            return;
+        }
         Footer.render(
             FooterProps {
-                total_bytes: *total_bytes,
+                total_bytes: traversal.total_bytes(state.size_kind),
+                size_kind: state.size_kind,
                 format: display.byte_format,
                 entries_traversed: *entries_traversed,
                 message: state.message.clone(),
                 traversal_start: *start,
                 elapsed: *elapsed,
+                theme: &state.theme,
             },
             footer_area,
             buf,
@@ -1,21 +1,25 @@
 use crate::interactive::{
-    fit_string_graphemes_with_ellipsis, path_of, widgets::entry_color, CursorDirection,
+    bytes_of, fit_string_graphemes_with_ellipsis, path_from_bytes, path_of, shell_quote,
+    widgets::{DeletionSummary, Theme},
+    Action, CursorDirection, KeyBindings, Scope,
 };
-use crosstermion::{input::Key, input::Key::*};
 use dua::{
-    traverse::{Tree, TreeIndex},
+    traverse::{SizeKind, Traversal, Tree, TreeIndex},
     ByteFormat,
 };
 use itertools::Itertools;
+use petgraph::Direction::Outgoing;
 use std::{
     borrow::Borrow,
-    collections::{btree_map::Entry, BTreeMap},
-    path::PathBuf,
+    collections::{btree_map::Entry, BTreeMap, BTreeSet, HashSet},
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
 };
 use tui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
     widgets::{Block, Borders, Paragraph, Widget},
 };
@@ -26,15 +30,21 @@ use tui_react::{
 };
 use unicode_segmentation::UnicodeSegmentation;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum MarkMode {
+    #[default]
     Delete,
     #[cfg(feature = "trash-move")]
     Trash,
 }
 
+/// The first line of lists of marked entries, naming the columns of the lines that follow.
+const MARK_LIST_HEADER: &str = "# size on disk\tapparent size\tpath";
+
 pub type EntryMarkMap = BTreeMap<TreeIndex, EntryMark>;
 pub struct EntryMark {
-    pub size: u128,
+    pub size_on_disk: u128,
+    pub apparent_size: u128,
     pub path: PathBuf,
     pub index: usize,
     pub num_errors_during_deletion: usize,
@@ -45,14 +55,36 @@ pub struct EntryMark {
 pub struct MarkPane {
     selected: Option<usize>,
     marked: EntryMarkMap,
+    /// The marked entries which are below another marked entry, as of the last call to [`MarkPane::refresh()`].
+    covered: BTreeSet<TreeIndex>,
+    /// The amount of bytes on disk and the apparent size deleting all marked entries frees,
+    /// as of the last call to [`MarkPane::refresh()`].
+    freed: (u128, u128),
+    /// If set, marks changed since [`MarkPane::refresh()`] was called.
+    is_stale: bool,
     list: List,
     has_focus: bool,
     last_sorting_index: usize,
+    /// How the marked entries were last asked to be removed, which scripts written for review do as well.
+    mode: MarkMode,
 }
 
-pub struct MarkPaneProps {
+pub struct MarkPaneProps<'a> {
     pub border_style: Style,
     pub format: ByteFormat,
+    pub size_kind: SizeKind,
+    pub bindings: &'a KeyBindings,
+    pub theme: &'a Theme,
+}
+
+impl EntryMark {
+    /// Return the size in bytes of the given `kind`.
+    pub fn size(&self, kind: SizeKind) -> u128 {
+        match kind {
+            SizeKind::OnDisk => self.size_on_disk,
+            SizeKind::Apparent => self.apparent_size,
+        }
+    }
 }
 
 impl MarkPane {
@@ -81,17 +113,20 @@ impl MarkPane {
                     let sorting_index = self.last_sorting_index + 1;
                     self.last_sorting_index = sorting_index;
                     entry.insert(EntryMark {
-                        size: e.size,
+                        size_on_disk: e.size_on_disk,
+                        apparent_size: e.apparent_size,
                         path: path_of(tree, index),
                         index: sorting_index,
                         num_errors_during_deletion: 0,
                         is_dir,
                     });
+                    self.is_stale = true;
                 }
             }
             Entry::Occupied(entry) => {
                 if toggle {
                     entry.remove();
+                    self.is_stale = true;
                 }
             }
         };
@@ -101,93 +136,215 @@ impl MarkPane {
             Some(self)
         }
     }
+    /// Remove the marks of all entries below `root`, but not the one of `root` itself.
+    pub fn unmark_below(mut self, root: TreeIndex, tree: &Tree) -> Option<Self> {
+        self.marked.retain(|index, _| !is_below(tree, *index, root));
+        self.is_stale = true;
+        let len = self.marked.len();
+        self.selected = self
+            .selected
+            .map(|selected| selected.min(len.saturating_sub(1)));
+        if self.marked.is_empty() {
+            None
+        } else {
+            Some(self)
+        }
+    }
     pub fn marked(&self) -> &EntryMarkMap {
         &self.marked
     }
+    /// Update what is derived from all marks if they changed since the last call,
+    /// which is done once after changing them instead of each time it is needed.
+    pub fn refresh(&mut self, traversal: &Traversal) {
+        if !self.is_stale {
+            return;
+        }
+        let paths: HashSet<&Path> = self.marked.values().map(|m| m.path.as_path()).collect();
+        self.covered = self
+            .marked
+            .iter()
+            .filter(|(_, m)| m.path.ancestors().skip(1).any(|p| paths.contains(p)))
+            .map(|(index, _)| *index)
+            .collect();
+        let uncovered = self
+            .marked
+            .keys()
+            .filter(|index| !self.covered.contains(index))
+            .copied()
+            .collect();
+        self.freed = (
+            traversal.freed_bytes(&uncovered, SizeKind::OnDisk),
+            traversal.freed_bytes(&uncovered, SizeKind::Apparent),
+        );
+        self.is_stale = false;
+    }
+    /// Return the marked entries which are below another marked entry, and thus deleted along with it.
+    #[cfg(test)]
+    pub fn covered(&self) -> &BTreeSet<TreeIndex> {
+        &self.covered
+    }
+    /// Return the amount of bytes of the given `kind` deleting all marked entries frees,
+    /// which excludes files with hard links that aren't marked.
+    pub fn freed(&self, kind: SizeKind) -> u128 {
+        match kind {
+            SizeKind::OnDisk => self.freed.0,
+            SizeKind::Apparent => self.freed.1,
+        }
+    }
+    /// Summarize what deleting all marked entries affects, with sizes of the given `kind`.
+    pub fn deletion_summary(&self, traversal: &Traversal, kind: SizeKind) -> DeletionSummary {
+        let tree = &traversal.tree;
+        let covered = &self.covered;
+        let mut summary = DeletionSummary {
+            files: 0,
+            directories: 0,
+            bytes: self.size(kind),
+            freed: self.freed(kind),
+            paths: Vec::new(),
+        };
+        for (index, mark) in self.marked_sorted_by_index() {
+            if covered.contains(index) {
+                continue;
+            }
+            summary.paths.push(mark.path.clone());
+            if mark.is_dir {
+                summary.directories += 1;
+            } else {
+                summary.files += 1;
+            }
+            let mut below: Vec<_> = tree.neighbors_directed(*index, Outgoing).collect();
+            while let Some(index) = below.pop() {
+                below.extend(tree.neighbors_directed(index, Outgoing));
+                if tree[index].is_dir {
+                    summary.directories += 1;
+                } else {
+                    summary.files += 1;
+                }
+            }
+        }
+        summary
+    }
+    /// Return the total size of the given `kind` of all marked entries, counting covered entries only once.
+    pub fn size(&self, kind: SizeKind) -> u128 {
+        self.marked
+            .iter()
+            .filter(|(index, _)| !self.covered.contains(index))
+            .map(|(_, m)| m.size(kind))
+            .sum()
+    }
+    /// Write all marked entries in the order they were marked, one per line with their sizes and absolute path.
+    /// Paths are escaped with [`escape_path()`] to keep them intact.
+    pub fn write_list(&self, mut out: impl io::Write) -> io::Result<()> {
+        let cwd = std::env::current_dir()?;
+        writeln!(out, "{}", MARK_LIST_HEADER)?;
+        for (_, mark) in self.marked_sorted_by_index() {
+            writeln!(
+                out,
+                "{}\t{}\t{}",
+                mark.size_on_disk,
+                mark.apparent_size,
+                escape_path(&cwd.join(&mark.path))
+            )?;
+        }
+        Ok(())
+    }
+    /// Write a POSIX shell script deleting or trashing all marked entries according to `mode`,
+    /// with their sizes of the given `kind` as comments. Covered entries are left to the directories containing them.
+    pub fn write_script(
+        &self,
+        mut out: impl io::Write,
+        mode: MarkMode,
+        kind: SizeKind,
+        format: ByteFormat,
+    ) -> io::Result<()> {
+        let cwd = std::env::current_dir()?;
+        let covered = &self.covered;
+        let command = match mode {
+            MarkMode::Delete => "rm -rf --",
+            #[cfg(feature = "trash-move")]
+            MarkMode::Trash => "gio trash --",
+        };
+        writeln!(out, "#!/bin/sh")?;
+        writeln!(
+            out,
+            "# {} marked entries with {}, written by dua for review before running",
+            self.marked.len() - covered.len(),
+            format.display(self.size(kind)).to_string().trim()
+        )?;
+        for (index, mark) in self.marked_sorted_by_index() {
+            if covered.contains(index) {
+                continue;
+            }
+            writeln!(out)?;
+            writeln!(
+                out,
+                "# {}",
+                format.display(mark.size(kind)).to_string().trim()
+            )?;
+            writeln!(out, "{} {}", command, shell_quote(&cwd.join(&mark.path)))?;
+        }
+        Ok(())
+    }
+    pub fn mode(&self) -> MarkMode {
+        self.mode
+    }
     pub fn into_paths(self) -> impl Iterator<Item = PathBuf> {
         self.marked.into_values().map(|v| v.path)
     }
-    pub fn process_events(mut self, key: Key) -> Option<(Self, Option<MarkMode>)> {
-        let action = None;
-        match key {
-            Ctrl('r') => return Some(self.prepare_deletion(MarkMode::Delete)),
+    pub fn process_events(mut self, action: Action) -> Option<(Self, Option<MarkMode>)> {
+        let mode = None;
+        match action {
+            Action::DeleteMarked => {
+                self.mode = MarkMode::Delete;
+                return Some((self, Some(MarkMode::Delete)));
+            }
             #[cfg(feature = "trash-move")]
-            Ctrl('t') => return Some(self.prepare_deletion(MarkMode::Trash)),
-            Char('x') | Char('d') | Char(' ') => {
-                return self.remove_selected().map(|s| (s, action))
+            Action::TrashMarked => {
+                self.mode = MarkMode::Trash;
+                return Some((self, Some(MarkMode::Trash)));
             }
-            Char('a') => return None,
-            Char('H') => self.change_selection(CursorDirection::ToTop),
-            Char('G') => self.change_selection(CursorDirection::ToBottom),
-            Ctrl('u') | PageUp => self.change_selection(CursorDirection::PageUp),
-            Char('k') | Up => self.change_selection(CursorDirection::Up),
-            Char('j') | Down => self.change_selection(CursorDirection::Down),
-            Ctrl('d') | PageDown => self.change_selection(CursorDirection::PageDown),
+            Action::RemoveMark => return self.remove_selected().map(|s| (s, mode)),
+            Action::RemoveAllMarks => return None,
+            Action::ToTop => self.change_selection(CursorDirection::ToTop),
+            Action::ToBottom => self.change_selection(CursorDirection::ToBottom),
+            Action::PageUp => self.change_selection(CursorDirection::PageUp),
+            Action::Up => self.change_selection(CursorDirection::Up),
+            Action::Down => self.change_selection(CursorDirection::Down),
+            Action::PageDown => self.change_selection(CursorDirection::PageDown),
             _ => {}
         };
-        Some((self, action))
+        Some((self, mode))
     }
 
-    pub fn iterate_deletable_items(
-        mut self,
-        mut delete_fn: impl FnMut(Self, TreeIndex) -> Result<Self, (Self, usize)>,
-    ) -> Option<Self> {
-        loop {
-            match self.next_entry_for_deletion() {
-                Some(entry_to_delete) => match delete_fn(self, entry_to_delete) {
-                    Ok(pane) => {
-                        self = pane;
-                        match self.delete_entry() {
-                            Some(p) => self = p,
-                            None => return None,
-                        }
-                    }
-                    Err((pane, num_errors)) => {
-                        self = pane;
-                        self.set_error_on_marked_item(num_errors)
-                    }
-                },
-                None => return Some(self),
-            }
+    /// Forget all marks of entries which are no longer in `tree`, and return `None` if no marks remain.
+    pub fn retain_existing(mut self, tree: &Tree) -> Option<Self> {
+        self.marked.retain(|index, _| tree.contains_node(*index));
+        self.is_stale = true;
+        if self.marked.is_empty() {
+            return None;
         }
+        self.selected = self.selected.map(|s| s.min(self.marked.len() - 1));
+        Some(self)
     }
-
-    fn next_entry_for_deletion(&mut self) -> Option<TreeIndex> {
-        match self.selected.and_then(|selected| {
-            self.tree_index_by_list_position(selected)
-                .and_then(|idx| self.marked.get(&idx).map(|d| (selected, idx, d)))
-        }) {
-            Some((position, selected_index, data)) => match data.num_errors_during_deletion {
-                0 => Some(selected_index),
-                _ => {
-                    self.selected = match position + 1 {
-                        p if p < self.marked.len() => Some(p),
-                        _ => Some(self.marked.len().saturating_sub(1)),
-                    };
-                    self.tree_index_by_list_position(position + 1)
-                }
-            },
-            None => None,
+    /// Record that deleting the entry marked at `index` failed with `num_errors`.
+    pub fn set_errors(&mut self, index: TreeIndex, num_errors: usize) {
+        if let Some(mark) = self.marked.get_mut(&index) {
+            mark.num_errors_during_deletion = num_errors;
         }
     }
-    fn delete_entry(self) -> Option<Self> {
-        self.remove_selected()
-    }
-    fn set_error_on_marked_item(&mut self, num_errors: usize) {
-        if let Some(d) = self
-            .selected
-            .and_then(|s| self.tree_index_by_list_position(s))
-            .and_then(|p| self.marked.get_mut(&p))
-        {
-            d.num_errors_during_deletion = num_errors;
+    /// Forget the marks of covered entries and the errors of previous deletions, as all marked entries are deleted now.
+    ///
+    /// Covered entries are deleted with the directory containing them, and must not be deleted twice.
+    pub fn start_deletion(&mut self, traversal: &Traversal) {
+        self.refresh(traversal);
+        for index in std::mem::take(&mut self.covered) {
+            self.marked.remove(&index);
         }
-    }
-    fn prepare_deletion(mut self, mark: MarkMode) -> (Self, Option<MarkMode>) {
+        self.is_stale = true;
         for entry in self.marked.values_mut() {
             entry.num_errors_during_deletion = 0;
         }
         self.selected = Some(0);
-        (self, Some(mark))
     }
     fn remove_selected(mut self) -> Option<Self> {
         if let Some(mut selected) = self.selected {
@@ -195,6 +352,7 @@ impl MarkPane {
             let se_len = self.marked.len();
             if let Some(idx) = idx {
                 self.marked.remove(&idx);
+                self.is_stale = true;
                 let new_len = se_len.saturating_sub(1);
                 if new_len == 0 {
                     return None;
@@ -214,7 +372,7 @@ impl MarkPane {
             .map(|(k, _)| *k.to_owned())
     }
 
-    fn marked_sorted_by_index(&self) -> Vec<(&TreeIndex, &EntryMark)> {
+    pub fn marked_sorted_by_index(&self) -> Vec<(&TreeIndex, &EntryMark)> {
         self.marked
             .iter()
             .sorted_by_key(|(_, v)| &v.index)
@@ -229,22 +387,40 @@ impl MarkPane {
         });
     }
 
-    pub fn render(&mut self, props: impl Borrow<MarkPaneProps>, area: Rect, buf: &mut Buffer) {
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<MarkPaneProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
         let MarkPaneProps {
             border_style,
             format,
+            size_kind,
+            bindings,
+            theme,
         } = props.borrow();
 
+        let covered = &self.covered;
         let marked: &_ = &self.marked;
         let title = format!(
-            "Marked {} items ({}) ",
+            "Marked {} items ({}, {} actually freed{}) ",
             marked.len(),
-            format.display(marked.iter().map(|(_k, v)| v.size).sum::<u128>())
+            format.display(self.size(*size_kind)),
+            format.display(self.freed(*size_kind)),
+            match covered.len() {
+                0 => String::new(),
+                n => format!(", {} covered", n),
+            }
         );
         let selected = self.selected;
         let has_focus = self.has_focus;
-        let entries = marked.values().sorted_by_key(|v| &v.index).enumerate().map(
-            |(idx, v): (usize, &EntryMark)| {
+        let entries = marked
+            .iter()
+            .sorted_by_key(|(_, v)| &v.index)
+            .enumerate()
+            .map(|(idx, (index, v)): (usize, (&TreeIndex, &EntryMark))| {
+                let is_covered = covered.contains(index);
                 let base_style = match selected {
                     Some(selected) if idx == selected => {
                         let mut modifier = Modifier::REVERSED;
@@ -264,6 +440,8 @@ impl MarkPane {
                         v.path.display(),
                         if v.num_errors_during_deletion != 0 {
                             format!("{} IO deletion errors", v.num_errors_during_deletion)
+                        } else if is_covered {
+                            "covered by a marked directory".to_string()
                         } else {
                             "".to_string()
                         }
@@ -281,22 +459,27 @@ impl MarkPane {
                         _ => (path, num_path_graphemes),
                     }
                 };
-                let fg_path = entry_color(None, !v.is_dir, true);
+                let fg_path = theme.entry_color(None, !v.is_dir, true);
                 let path = Span::styled(
                     path,
                     Style {
                         fg: fg_path,
                         ..base_style
-                    },
+                    }
+                    .add_modifier(if is_covered {
+                        Modifier::DIM
+                    } else {
+                        theme.marked_modifier
+                    }),
                 );
                 let bytes = Span::styled(
                     format!(
                         "{:>byte_column_width$} ",
-                        format.display(v.size).to_string(), // we would have to impl alignment/padding ourselves otherwise...
+                        format.display(v.size(*size_kind)).to_string(), // we would have to impl alignment/padding ourselves otherwise...
                         byte_column_width = format.width()
                     ),
                     Style {
-                        fg: Color::Green.into(),
+                        fg: theme.size.into(),
                         ..base_style
                     },
                 );
@@ -314,8 +497,7 @@ impl MarkPane {
                     },
                 );
                 vec![path, spacer, bytes]
-            },
-        );
+            });
 
         let entry_in_view = match self.selected {
             Some(s) => Some(s),
@@ -355,33 +537,33 @@ impl MarkPane {
             };
 
             let default_style = Style {
-                fg: Color::Black.into(),
-                bg: Color::Yellow.into(),
+                fg: theme.hint_fg.into(),
+                bg: theme.hint_bg.into(),
                 add_modifier: Modifier::BOLD,
                 sub_modifier: Modifier::empty(),
             };
             Paragraph::new(Text::from(Spans::from(vec![
                 #[cfg(feature = "trash-move")]
                 Span::styled(
-                    " Ctrl + t ",
+                    format!(" {} ", bindings.key_name(Scope::Mark, Action::TrashMarked)),
                     Style {
-                        fg: Color::White.into(),
-                        bg: Color::Black.into(),
+                        fg: theme.hint_key_fg.into(),
+                        bg: theme.hint_key_bg.into(),
                         ..default_style
                     },
                 ),
                 #[cfg(feature = "trash-move")]
                 Span::styled(" to trash or ", default_style),
                 Span::styled(
-                    " Ctrl + r ",
+                    format!(" {} ", bindings.key_name(Scope::Mark, Action::DeleteMarked)),
                     Style {
-                        fg: Color::LightRed.into(),
-                        bg: Color::Black.into(),
+                        fg: theme.danger.into(),
+                        bg: theme.hint_key_bg.into(),
                         add_modifier: default_style.add_modifier | Modifier::RAPID_BLINK,
                         ..default_style
                     },
                 ),
-                Span::styled(" to delete without prompt", default_style),
+                Span::styled(" to delete", default_style),
             ])))
             .style(default_style)
             .render(help_line_area, buf);
@@ -397,7 +579,8 @@ impl MarkPane {
         self.list.render(props, entries, list_area, buf);
 
         if has_focus {
-            let help_text = " . = o|.. = u ── ⇊ = Ctrl+d|↓ = j|⇈ = Ctrl+u|↑ = k ";
+            let help_text = bindings.navigation_hint(Scope::Mark, false);
+            let help_text = help_text.as_str();
             let help_text_block_width = block_width(help_text);
             let bound = Rect {
                 width: area.width.saturating_sub(1),
@@ -412,7 +595,14 @@ impl MarkPane {
                 );
             }
             let bound = line_bound(bound, bound.height.saturating_sub(1) as usize);
-            let help_text = " mark-toggle = space,d | remove-all = a";
+            let help_text = bindings.hint(
+                Scope::Mark,
+                &[
+                    ("mark-toggle", Action::RemoveMark),
+                    ("remove-all", Action::RemoveAllMarks),
+                ],
+            );
+            let help_text = help_text.as_str();
             let help_text_block_width = block_width(help_text);
             if help_text_block_width <= bound.width {
                 draw_text_nowrap_fn(
@@ -425,3 +615,85 @@ impl MarkPane {
         }
     }
 }
+
+/// Return the paths of a list of marked entries as written by [`MarkPane::write_list()`].
+///
+/// Lines without sizes are taken as paths as a whole, so plain lists of paths can be read as well.
+pub fn read_mark_list(list: &str) -> Vec<PathBuf> {
+    list.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(on_disk), Some(apparent), Some(path))
+                    if on_disk.parse::<u128>().is_ok() && apparent.parse::<u128>().is_ok() =>
+                {
+                    unescape_path(path)
+                }
+                _ => line.into(),
+            }
+        })
+        .collect()
+}
+
+/// Escape `path` for a line of a mark list, with backslashes, tabs, newlines and carriage returns as `\\`, `\t`,
+/// `\n` and `\r`, and bytes that aren't valid UTF-8 as `\xHH`.
+fn escape_path(path: &Path) -> String {
+    let mut escaped = String::new();
+    for chunk in bytes_of(path).utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\t' => escaped.push_str("\\t"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                c => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            write!(escaped, "\\x{:02x}", byte).expect("writing to a string to succeed");
+        }
+    }
+    escaped
+}
+
+/// Return the path escaped with [`escape_path()`]. Backslashes that don't start an escape are kept.
+fn unescape_path(escaped: &str) -> PathBuf {
+    let mut rest = escaped.as_bytes();
+    let mut bytes = Vec::with_capacity(rest.len());
+    while !rest.is_empty() {
+        let (byte, len) = match rest {
+            [b'\\', b'\\', ..] => (b'\\', 2),
+            [b'\\', b't', ..] => (b'\t', 2),
+            [b'\\', b'n', ..] => (b'\n', 2),
+            [b'\\', b'r', ..] => (b'\r', 2),
+            [b'\\', b'x', hex @ ..] => match hex
+                .get(..2)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => (byte, 4),
+                None => (b'\\', 1),
+            },
+            _ => (rest[0], 1),
+        };
+        bytes.push(byte);
+        rest = &rest[len..];
+    }
+    path_from_bytes(bytes)
+}
+
+/// Return `true` if `ancestor` is a parent of `index`, or one of its parents.
+fn is_below(tree: &Tree, mut index: TreeIndex, ancestor: TreeIndex) -> bool {
+    while let Some(parent) = tree
+        .neighbors_directed(index, petgraph::Direction::Incoming)
+        .next()
+    {
+        if parent == ancestor {
+            return true;
+        }
+        index = parent;
+    }
+    false
+}
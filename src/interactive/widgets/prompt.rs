@@ -0,0 +1,61 @@
+use crate::interactive::widgets::Theme;
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Paragraph, Widget},
+};
+
+/// A line to edit text in, shown instead of the footer.
+pub struct Prompt;
+
+pub struct PromptProps<'a> {
+    /// Describes what the input is used for.
+    pub title: String,
+    pub input: &'a str,
+    /// Information about the input, shown after it.
+    pub status: Option<String>,
+    /// If set, the status is shown like an error message.
+    pub is_error: bool,
+    pub theme: &'a Theme,
+}
+
+impl Prompt {
+    pub fn render<'a>(&self, props: impl Borrow<PromptProps<'a>>, area: Rect, buf: &mut Buffer) {
+        let PromptProps {
+            title,
+            input,
+            status,
+            is_error,
+            theme,
+        } = props.borrow();
+
+        let spans = vec![
+            Some(Span::styled(
+                format!(" {title}: "),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Some(Span::raw(*input)),
+            Some(Span::styled(
+                " ",
+                Style::default().add_modifier(Modifier::REVERSED),
+            )),
+            status.as_ref().map(|status| {
+                Span::styled(
+                    format!("  {status}"),
+                    if *is_error {
+                        Style::default().fg(theme.message)
+                    } else {
+                        Style::default().add_modifier(Modifier::ITALIC)
+                    },
+                )
+            }),
+        ];
+        Paragraph::new(Text::from(Spans::from(
+            spans.into_iter().flatten().collect::<Vec<_>>(),
+        )))
+        .render(area, buf);
+    }
+}
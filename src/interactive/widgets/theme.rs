@@ -0,0 +1,434 @@
+use anyhow::{bail, Context, Result};
+use lscolors::{Colorable, LsColors};
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs::{FileType, Metadata},
+    path::{Path, PathBuf},
+};
+use tui::style::{Color, Modifier, Style};
+
+/// A built-in set of colors to base a theme on.
+#[derive(Default, Debug, Clone, Copy)]
+pub enum ThemePreset {
+    /// Colors for terminals with a dark background.
+    #[default]
+    Dark,
+    /// Colors for terminals with a light background.
+    Light,
+    /// Bright colors with maximal contrast, which don't rely on telling red and green apart.
+    HighContrast,
+    /// No colors at all, for when colors are disabled.
+    Monochrome,
+}
+
+/// The colors used by all widgets.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// The names of directories.
+    pub directory: Color,
+    /// The names of marked directories, and the background of the header if there are marked entries.
+    pub marked: Color,
+    /// The names of marked files.
+    pub marked_file: Color,
+    /// The names of entries that don't exist anymore.
+    pub missing: Color,
+    /// Byte counts.
+    pub size: Color,
+    pub header_fg: Color,
+    pub header_bg: Color,
+    /// Everything related to deletion, like the header background while the mark pane has focus.
+    pub danger: Color,
+    /// The footer foreground. If it's reset along with `footer_bg`, the terminal colors are used in reverse.
+    pub footer_fg: Color,
+    pub footer_bg: Color,
+    /// Messages in the footer.
+    pub message: Color,
+    /// The borders of panes without focus.
+    pub inactive_border: Color,
+    /// Keys in the help pane.
+    pub help_key: Color,
+    /// The line with hints about how to delete marked entries.
+    pub hint_fg: Color,
+    pub hint_bg: Color,
+    pub hint_key_fg: Color,
+    pub hint_key_bg: Color,
+    /// The names of entries matching the current search.
+    pub search_match: Color,
+    /// The backgrounds of the rectangles of the treemap, used in turn. If empty, shades of the terminal colors are used.
+    pub treemap: Vec<Color>,
+    /// Added to the style of names of marked entries, to tell them apart without colors.
+    pub marked_modifier: Modifier,
+    /// If set, names of unmarked entries are colored like `ls` would.
+    pub ls_colors: Option<LsColors>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        ThemePreset::default().into()
+    }
+}
+
+impl From<ThemePreset> for Theme {
+    fn from(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Theme {
+                directory: Color::Cyan,
+                marked: Color::Yellow,
+                marked_file: Color::Rgb(176, 126, 0),
+                missing: Color::Red,
+                size: Color::Green,
+                header_fg: Color::Black,
+                header_bg: Color::White,
+                danger: Color::LightRed,
+                footer_fg: Color::Reset,
+                footer_bg: Color::Reset,
+                message: Color::Red,
+                inactive_border: Color::DarkGray,
+                help_key: Color::Green,
+                hint_fg: Color::Black,
+                hint_bg: Color::Yellow,
+                hint_key_fg: Color::White,
+                hint_key_bg: Color::Black,
+                search_match: Color::LightMagenta,
+                treemap: vec![
+                    Color::Blue,
+                    Color::Magenta,
+                    Color::Cyan,
+                    Color::Green,
+                    Color::Yellow,
+                    Color::Red,
+                ],
+                marked_modifier: Modifier::empty(),
+                ls_colors: None,
+            },
+            ThemePreset::Light => Theme {
+                directory: Color::Blue,
+                marked: Color::Magenta,
+                marked_file: Color::Rgb(135, 0, 135),
+                missing: Color::Red,
+                size: Color::Rgb(0, 110, 0),
+                header_fg: Color::White,
+                header_bg: Color::DarkGray,
+                danger: Color::Red,
+                footer_fg: Color::Reset,
+                footer_bg: Color::Reset,
+                message: Color::Red,
+                inactive_border: Color::Gray,
+                help_key: Color::Rgb(0, 110, 0),
+                hint_fg: Color::White,
+                hint_bg: Color::Magenta,
+                hint_key_fg: Color::White,
+                hint_key_bg: Color::Black,
+                search_match: Color::Rgb(190, 80, 0),
+                treemap: vec![
+                    Color::Rgb(120, 160, 220),
+                    Color::Rgb(200, 150, 210),
+                    Color::Rgb(120, 200, 200),
+                    Color::Rgb(150, 200, 130),
+                    Color::Rgb(230, 200, 110),
+                    Color::Rgb(230, 140, 130),
+                ],
+                marked_modifier: Modifier::empty(),
+                ls_colors: None,
+            },
+            ThemePreset::HighContrast => Theme {
+                directory: Color::LightCyan,
+                marked: Color::LightYellow,
+                marked_file: Color::Yellow,
+                missing: Color::LightMagenta,
+                size: Color::White,
+                header_fg: Color::Black,
+                header_bg: Color::White,
+                danger: Color::LightMagenta,
+                footer_fg: Color::Black,
+                footer_bg: Color::White,
+                message: Color::LightMagenta,
+                inactive_border: Color::Gray,
+                help_key: Color::LightYellow,
+                hint_fg: Color::Black,
+                hint_bg: Color::LightYellow,
+                hint_key_fg: Color::White,
+                hint_key_bg: Color::Black,
+                search_match: Color::LightBlue,
+                treemap: vec![
+                    Color::LightBlue,
+                    Color::LightMagenta,
+                    Color::LightCyan,
+                    Color::White,
+                    Color::LightYellow,
+                ],
+                marked_modifier: Modifier::empty(),
+                ls_colors: None,
+            },
+            ThemePreset::Monochrome => Theme {
+                directory: Color::Reset,
+                marked: Color::Reset,
+                marked_file: Color::Reset,
+                missing: Color::Reset,
+                size: Color::Reset,
+                header_fg: Color::Reset,
+                header_bg: Color::Reset,
+                danger: Color::Reset,
+                footer_fg: Color::Reset,
+                footer_bg: Color::Reset,
+                message: Color::Reset,
+                inactive_border: Color::Reset,
+                help_key: Color::Reset,
+                hint_fg: Color::Reset,
+                hint_bg: Color::Reset,
+                hint_key_fg: Color::Reset,
+                hint_key_bg: Color::Reset,
+                search_match: Color::Reset,
+                treemap: Vec::new(),
+                marked_modifier: Modifier::UNDERLINED,
+                ls_colors: None,
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// Create the theme for `preset` with colors overridden by `colors`, which maps color names to colors.
+    pub fn from_config(preset: ThemePreset, colors: &BTreeMap<String, String>) -> Result<Self> {
+        let mut theme = Theme::from(preset);
+        for (name, color) in colors {
+            let target = theme
+                .color_mut(name)
+                .with_context(|| format!("Unknown theme color '{name}'"))?;
+            *target = parse_color(color).with_context(|| format!("Invalid color for '{name}'"))?;
+        }
+        Ok(theme)
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "directory" => &mut self.directory,
+            "marked" => &mut self.marked,
+            "marked-file" => &mut self.marked_file,
+            "missing" => &mut self.missing,
+            "size" => &mut self.size,
+            "header-fg" => &mut self.header_fg,
+            "header-bg" => &mut self.header_bg,
+            "danger" => &mut self.danger,
+            "footer-fg" => &mut self.footer_fg,
+            "footer-bg" => &mut self.footer_bg,
+            "message" => &mut self.message,
+            "inactive-border" => &mut self.inactive_border,
+            "help-key" => &mut self.help_key,
+            "hint-fg" => &mut self.hint_fg,
+            "hint-bg" => &mut self.hint_bg,
+            "hint-key-fg" => &mut self.hint_key_fg,
+            "hint-key-bg" => &mut self.hint_key_bg,
+            "search-match" => &mut self.search_match,
+            _ => return None,
+        })
+    }
+
+    /// The color of an entry's name, or `fg` if it has no special color.
+    pub fn entry_color(&self, fg: Option<Color>, is_file: bool, is_marked: bool) -> Option<Color> {
+        match (is_file, is_marked) {
+            (true, false) => fg,
+            (true, true) => self.marked_file.into(),
+            (false, true) => self.marked.into(),
+            (false, false) => self.directory.into(),
+        }
+    }
+
+    /// The style of the name of an unmarked entry with the given `name` and `metadata` according to `LS_COLORS`,
+    /// if enabled. Its `path` is only needed to see if symlinks lead anywhere.
+    pub fn ls_style(
+        &self,
+        name: &Path,
+        metadata: Option<&Metadata>,
+        path: impl Fn() -> PathBuf,
+    ) -> Option<Style> {
+        let ls_colors = self.ls_colors.as_ref()?;
+        let style = ls_colors.style_for(&ListedEntry {
+            name,
+            metadata,
+            path,
+        })?;
+        let mut modifier = Modifier::empty();
+        let font = style.font_style;
+        for (is_set, flag) in [
+            (font.bold, Modifier::BOLD),
+            (font.dimmed, Modifier::DIM),
+            (font.italic, Modifier::ITALIC),
+            (font.underline, Modifier::UNDERLINED),
+            (font.strikethrough, Modifier::CROSSED_OUT),
+        ] {
+            if is_set {
+                modifier.insert(flag);
+            }
+        }
+        Some(Style {
+            fg: style.foreground.map(from_ls_color),
+            add_modifier: modifier,
+            ..Style::default()
+        })
+    }
+
+    /// The style of the footer, which is the reversed terminal style if no colors are set.
+    pub fn footer_style(&self) -> Style {
+        match (self.footer_fg, self.footer_bg) {
+            (Color::Reset, Color::Reset) => Style::default().add_modifier(Modifier::REVERSED),
+            (fg, bg) => Style::default().fg(fg).bg(bg),
+        }
+    }
+}
+
+/// An entry as listed, whose metadata was read already.
+struct ListedEntry<'a, P> {
+    name: &'a Path,
+    metadata: Option<&'a Metadata>,
+    path: P,
+}
+
+impl<P: Fn() -> PathBuf> Colorable for ListedEntry<'_, P> {
+    fn path(&self) -> PathBuf {
+        (self.path)()
+    }
+
+    fn file_name(&self) -> OsString {
+        self.name
+            .file_name()
+            .unwrap_or(self.name.as_os_str())
+            .to_owned()
+    }
+
+    fn file_type(&self) -> Option<FileType> {
+        self.metadata.map(Metadata::file_type)
+    }
+
+    fn metadata(&self) -> Option<Metadata> {
+        self.metadata.cloned()
+    }
+}
+
+fn from_ls_color(color: lscolors::Color) -> Color {
+    use lscolors::Color::*;
+    match color {
+        Black => Color::Black,
+        Red => Color::Red,
+        Green => Color::Green,
+        Yellow => Color::Yellow,
+        Blue => Color::Blue,
+        Magenta => Color::Magenta,
+        Cyan => Color::Cyan,
+        White => Color::Gray,
+        BrightBlack => Color::DarkGray,
+        BrightRed => Color::LightRed,
+        BrightGreen => Color::LightGreen,
+        BrightYellow => Color::LightYellow,
+        BrightBlue => Color::LightBlue,
+        BrightMagenta => Color::LightMagenta,
+        BrightCyan => Color::LightCyan,
+        BrightWhite => Color::White,
+        Fixed(n) => Color::Indexed(n),
+        RGB(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
+
+/// Parse a color name like `light-red`, a hex color like `#b07e00` or an index into the 256 color palette.
+fn parse_color(input: &str) -> Result<Color> {
+    if let Some(hex) = input.strip_prefix('#') {
+        if hex.len() != 6 {
+            bail!("Expected 6 hexadecimal digits in '{input}'");
+        }
+        let channel = |range| {
+            u8::from_str_radix(&hex[range], 16)
+                .with_context(|| format!("Invalid hexadecimal color '{input}'"))
+        };
+        return Ok(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?));
+    }
+    if let Ok(index) = input.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+    Ok(
+        match input.to_ascii_lowercase().replace('_', "-").as_str() {
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "dark-gray" | "dark-grey" => Color::DarkGray,
+            "light-red" => Color::LightRed,
+            "light-green" => Color::LightGreen,
+            "light-yellow" => Color::LightYellow,
+            "light-blue" => Color::LightBlue,
+            "light-magenta" => Color::LightMagenta,
+            "light-cyan" => Color::LightCyan,
+            "white" => Color::White,
+            _ => bail!("Unknown color '{input}'"),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_of_a_preset_can_be_overridden() -> Result<()> {
+        let colors = [
+            ("directory", "light-blue"),
+            ("marked", "#b07e00"),
+            ("size", "208"),
+        ]
+        .iter()
+        .map(|(name, color)| (name.to_string(), color.to_string()))
+        .collect();
+        let theme = Theme::from_config(ThemePreset::Light, &colors)?;
+        assert_eq!(theme.directory, Color::LightBlue);
+        assert_eq!(theme.marked, Color::Rgb(176, 126, 0));
+        assert_eq!(theme.size, Color::Indexed(208));
+        assert_eq!(
+            theme.header_bg,
+            Theme::from(ThemePreset::Light).header_bg,
+            "everything else is from the preset"
+        );
+
+        for (name, color) in [("directory", "ultraviolet"), ("dir", "red")] {
+            let colors = std::iter::once((name.to_string(), color.to_string())).collect();
+            assert!(Theme::from_config(ThemePreset::Dark, &colors).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ls_colors_are_used_for_names_if_enabled() -> Result<()> {
+        let file = Path::new("Cargo.toml").symlink_metadata()?;
+        let dir = Path::new("src").symlink_metadata()?;
+        let no_path = || -> PathBuf { unreachable!("only symlinks need their path") };
+        let mut theme = Theme::default();
+        assert_eq!(
+            theme.ls_style(Path::new("a.tar"), Some(&file), no_path),
+            None
+        );
+
+        theme.ls_colors = Some(LsColors::from_string("di=01;34:*.tar=31"));
+        assert_eq!(
+            theme.ls_style(Path::new("a.tar"), Some(&file), no_path),
+            Some(Style::default().fg(Color::Red))
+        );
+        assert_eq!(
+            theme.ls_style(Path::new("missing.tar"), None, no_path),
+            Some(Style::default().fg(Color::Red)),
+            "names are styled without metadata as well"
+        );
+        assert_eq!(
+            theme.ls_style(Path::new("dir"), Some(&dir), no_path),
+            Some(
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD)
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+use crate::interactive::{path_of, widgets::Theme, Action};
+use dua::traverse::{SizeKind, Tree, TreeIndex};
+use itertools::Itertools;
+use petgraph::Direction;
+use std::borrow::Borrow;
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Widget},
+};
+use tui_react::draw_text_nowrap_fn;
+
+/// Shades to fill rectangles with, to tell them apart without colors and to show the entries of directories.
+const SHADES: [&str; 3] = ["░", "▒", "▓"];
+
+/// A pane showing the entries of a directory as rectangles whose area is proportional to their size,
+/// with the entries of directories nested into them.
+#[derive(Default)]
+pub struct Treemap {
+    /// The rectangles of all top-level entries as rendered last, to navigate between them.
+    rects: Vec<(TreeIndex, Rect)>,
+}
+
+pub struct TreemapProps<'a> {
+    pub tree: &'a Tree,
+    pub root: TreeIndex,
+    pub selected: Option<TreeIndex>,
+    pub size_kind: SizeKind,
+    pub border_style: Style,
+    pub theme: &'a Theme,
+}
+
+impl Treemap {
+    /// Return the entry whose rectangle lies next to the one of `selected` in the direction given by `action`,
+    /// or the first entry if `selected` isn't shown.
+    pub fn neighbour(&self, selected: Option<TreeIndex>, action: Action) -> Option<TreeIndex> {
+        let current = match selected.and_then(|s| self.rects.iter().find(|(idx, _)| *idx == s)) {
+            Some((_, rect)) => *rect,
+            None => return self.rects.first().map(|(idx, _)| *idx),
+        };
+        // Distances are measured between the centers of rectangles, in half cells.
+        let center = |r: &Rect| {
+            (
+                2 * r.x as i32 + r.width as i32,
+                2 * r.y as i32 + r.height as i32,
+            )
+        };
+        let (cx, cy) = center(&current);
+        self.rects
+            .iter()
+            .filter(|(_, r)| match action {
+                Action::SelectLeft => r.right() <= current.left(),
+                Action::SelectRight => r.left() >= current.right(),
+                Action::SelectAbove => r.bottom() <= current.top(),
+                Action::SelectBelow => r.top() >= current.bottom(),
+                _ => false,
+            })
+            .min_by_key(|(_, r)| {
+                let (x, y) = center(r);
+                let (along, across) = match action {
+                    Action::SelectLeft | Action::SelectRight => ((x - cx).abs(), (y - cy).abs()),
+                    _ => ((y - cy).abs(), (x - cx).abs()),
+                };
+                // Rectangles in line with the current one are preferred over closer ones that are offset.
+                along + 2 * across
+            })
+            .map(|(idx, _)| *idx)
+    }
+
+    pub fn render<'a>(
+        &mut self,
+        props: impl Borrow<TreemapProps<'a>>,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let TreemapProps {
+            tree,
+            root,
+            selected,
+            size_kind,
+            border_style,
+            theme,
+        } = props.borrow();
+
+        let title = format!(" Treemap of {} ", path_of(tree, *root).display());
+        let block = Block::default()
+            .title(title.as_str())
+            .border_style(*border_style)
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        self.rects = layout(tree, *root, *size_kind, inner);
+        for (pos, (idx, rect)) in self.rects.iter().enumerate() {
+            let (style, shade) = match theme.treemap.get(pos % theme.treemap.len().max(1)) {
+                Some(color) => (
+                    Style {
+                        fg: Color::Black.into(),
+                        bg: (*color).into(),
+                        ..Style::default()
+                    },
+                    " ",
+                ),
+                None => (Style::default(), SHADES[pos % 2]),
+            };
+            fill(buf, *rect, shade, style);
+
+            let nested = Rect {
+                y: rect.y + 1,
+                height: rect.height.saturating_sub(1),
+                ..*rect
+            };
+            if nested.width >= 4 && nested.height >= 2 {
+                for (child_pos, (_, child_rect)) in layout(tree, *idx, *size_kind, nested)
+                    .into_iter()
+                    .enumerate()
+                {
+                    fill(buf, child_rect, SHADES[child_pos % SHADES.len()], style);
+                }
+            }
+
+            let entry = &tree[*idx];
+            let label = format!(
+                "{} {}",
+                entry
+                    .name
+                    .file_name()
+                    .unwrap_or(entry.name.as_os_str())
+                    .to_string_lossy(),
+                display_size(entry.size(*size_kind)),
+            );
+            let label_style = if *selected == Some(*idx) {
+                style.add_modifier(Modifier::REVERSED | Modifier::BOLD)
+            } else {
+                style
+            };
+            draw_text_nowrap_fn(Rect { height: 1, ..*rect }, buf, label, |_, _, _| {
+                label_style
+            });
+        }
+    }
+}
+
+fn fill(buf: &mut Buffer, area: Rect, symbol: &str, style: Style) {
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            buf.get_mut(x, y).set_symbol(symbol).set_style(style);
+        }
+    }
+}
+
+/// A compact size for labels, which have little space.
+fn display_size(bytes: u128) -> String {
+    dua::ByteFormat::Metric
+        .display(bytes)
+        .to_string()
+        .split_whitespace()
+        .join("")
+}
+
+/// Lay out the entries of `node_idx` in `area`, largest first, skipping those that are too small to be seen.
+fn layout(
+    tree: &Tree,
+    node_idx: TreeIndex,
+    size_kind: SizeKind,
+    area: Rect,
+) -> Vec<(TreeIndex, Rect)> {
+    let entries: Vec<_> = tree
+        .neighbors_directed(node_idx, Direction::Outgoing)
+        .map(|idx| (idx, tree[idx].size(size_kind)))
+        .filter(|(_, size)| *size > 0)
+        .sorted_by(|(_, l), (_, r)| r.cmp(l))
+        .collect();
+    // Cells are about twice as high as they are wide, so heights are doubled to make rectangles look square.
+    let cells = FRect {
+        x: 0.0,
+        y: 0.0,
+        width: area.width as f64,
+        height: area.height as f64 * 2.0,
+    };
+    let sizes: Vec<_> = entries.iter().map(|(_, size)| *size as f64).collect();
+    entries
+        .iter()
+        .zip(squarify(&sizes, cells))
+        .filter_map(|((idx, _), r)| {
+            let (left, right) = (r.x.round() as u16, (r.x + r.width).round() as u16);
+            let (top, bottom) = (
+                (r.y / 2.0).round() as u16,
+                ((r.y + r.height) / 2.0).round() as u16,
+            );
+            (right > left && bottom > top).then(|| {
+                (
+                    *idx,
+                    Rect {
+                        x: area.x + left,
+                        y: area.y + top,
+                        width: (right - left).min(area.width - left),
+                        height: (bottom - top).min(area.height - top),
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FRect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Divide `area` into one rectangle per size in `sizes`, which must be sorted in descending order,
+/// such that their areas are proportional to the sizes and their aspect ratios are as close to 1 as possible.
+///
+/// This is the algorithm by Bruls, Huizing and van Wijk: rows of rectangles are placed along the shorter side
+/// of the remaining area, and each row takes as many rectangles as improve its worst aspect ratio.
+fn squarify(sizes: &[f64], area: FRect) -> Vec<FRect> {
+    fn worst_ratio(row: &[f64], side: f64) -> f64 {
+        let sum: f64 = row.iter().sum();
+        let (min, max) = row.iter().fold((f64::MAX, f64::MIN), |(min, max), a| {
+            (min.min(*a), max.max(*a))
+        });
+        let (side, sum) = (side * side, sum * sum);
+        (side * max / sum).max(sum / (side * min))
+    }
+
+    let total: f64 = sizes.iter().sum();
+    if total <= 0.0 || area.width <= 0.0 || area.height <= 0.0 {
+        return vec![
+            FRect {
+                width: 0.0,
+                height: 0.0,
+                ..area
+            };
+            sizes.len()
+        ];
+    }
+    let scale = area.width * area.height / total;
+    let areas: Vec<_> = sizes.iter().map(|size| size * scale).collect();
+
+    let mut out = Vec::with_capacity(areas.len());
+    let mut rest = area;
+    let mut start = 0;
+    while start < areas.len() {
+        let side = rest.width.min(rest.height);
+        let mut end = start + 1;
+        while end < areas.len()
+            && worst_ratio(&areas[start..=end], side) <= worst_ratio(&areas[start..end], side)
+        {
+            end += 1;
+        }
+        let row = &areas[start..end];
+        let row_area: f64 = row.iter().sum();
+        if rest.width >= rest.height {
+            // a column at the left
+            let width = if rest.height > 0.0 {
+                row_area / rest.height
+            } else {
+                0.0
+            };
+            let mut y = rest.y;
+            for a in row {
+                let height = if width > 0.0 { a / width } else { 0.0 };
+                out.push(FRect {
+                    x: rest.x,
+                    y,
+                    width,
+                    height,
+                });
+                y += height;
+            }
+            rest.x += width;
+            rest.width = (rest.width - width).max(0.0);
+        } else {
+            // a row at the top
+            let height = if rest.width > 0.0 {
+                row_area / rest.width
+            } else {
+                0.0
+            };
+            let mut x = rest.x;
+            for a in row {
+                let width = if height > 0.0 { a / height } else { 0.0 };
+                out.push(FRect {
+                    x,
+                    y: rest.y,
+                    width,
+                    height,
+                });
+                x += width;
+            }
+            rest.y += height;
+            rest.height = (rest.height - height).max(0.0);
+        }
+        start = end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squarified_rectangles_fill_the_area_proportionally() {
+        let area = FRect {
+            x: 0.0,
+            y: 0.0,
+            width: 6.0,
+            height: 4.0,
+        };
+        let sizes = [6.0, 6.0, 4.0, 3.0, 2.0, 2.0, 1.0];
+        let rects = squarify(&sizes, area);
+        assert_eq!(rects.len(), sizes.len());
+        for (size, r) in sizes.iter().zip(&rects) {
+            assert!((r.width * r.height - size).abs() < 1e-9, "{:?}", r);
+            assert!(r.x >= 0.0 && r.x + r.width <= 6.0 + 1e-9, "{:?}", r);
+            assert!(r.y >= 0.0 && r.y + r.height <= 4.0 + 1e-9, "{:?}", r);
+        }
+        assert_eq!(
+            rects[0],
+            FRect {
+                x: 0.0,
+                y: 0.0,
+                width: 3.0,
+                height: 2.0
+            },
+            "this is the example of the original paper"
+        );
+    }
+}
@@ -7,9 +7,13 @@ mod aggregate;
 mod common;
 mod crossdev;
 mod inodefilter;
+mod report;
+mod sort;
 
 pub mod traverse;
 
-pub use aggregate::aggregate;
+pub use aggregate::{aggregate, OutputStyle};
 pub use common::*;
-pub(crate) use inodefilter::InodeFilter;
+pub(crate) use inodefilter::{dev_inode_nlinks, InodeFilter};
+pub use report::{html_report, ReportOptions};
+pub use sort::{natural_cmp, SortMode, Sortable};
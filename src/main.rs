@@ -1,9 +1,9 @@
 #![forbid(unsafe_code)]
 use anyhow::Result;
-use clap::Parser;
 use dua::TraversalSorting;
 use std::{fs, io, io::Write, path::PathBuf, process};
 
+mod config;
 mod crossdev;
 #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
 mod interactive;
@@ -20,23 +20,54 @@ fn stderr_if_tty() -> Option<io::Stderr> {
 fn main() -> Result<()> {
     use options::Command::*;
 
-    let opt: options::Args = options::Args::parse_from(wild::args_os());
+    #[cfg_attr(
+        not(any(feature = "tui-unix", feature = "tui-crossplatform")),
+        allow(unused_variables)
+    )]
+    let (opt, config) = config::parse_args(wild::args_os())?;
     let walk_options = dua::WalkOptions {
         threads: opt.threads,
         byte_format: opt.format.into(),
         apparent_size: opt.apparent_size,
         count_hard_links: opt.count_hard_links,
+        count_directory_sizes: !opt.no_directory_sizes,
         sorting: TraversalSorting::None,
         cross_filesystems: !opt.stay_on_filesystem,
         ignore_dirs: opt.ignore_dirs,
     };
+    let output_style = {
+        let colored = opt.color.is_enabled(atty::is(atty::Stream::Stdout));
+        dua::OutputStyle {
+            colored,
+            hyperlinks: colored && opt.hyperlinks,
+        }
+    };
+    let aggregate_sorting = opt
+        .sort
+        .map(Into::into)
+        .unwrap_or(dua::SortMode::SizeAscending);
     let res = match opt.command {
         #[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
         Some(Interactive { input }) => {
-            use crate::interactive::{Interaction, TerminalApp};
+            use crate::interactive::{
+                widgets::{Theme, ThemePreset},
+                AppOptions, Interaction, KeyBindings, TerminalApp,
+            };
             use anyhow::{anyhow, Context};
             use crosstermion::terminal::{tui::new_terminal, AlternateRawScreen};
 
+            let bindings = KeyBindings::from_config(&config.keys)
+                .context("Invalid key bindings in configuration file")?;
+            let theme = if opt.color.is_enabled(true) {
+                let mut theme = Theme::from_config(opt.theme.into(), &config.colors)
+                    .context("Invalid colors in configuration file")?;
+                if opt.ls_colors {
+                    theme.ls_colors = lscolors::LsColors::from_env();
+                }
+                theme
+            } else {
+                ThemePreset::Monochrome.into()
+            };
             let no_tty_msg = "Interactive mode requires a connected terminal";
             if atty::isnt(atty::Stream::Stderr) {
                 return Err(anyhow!(no_tty_msg));
@@ -49,11 +80,22 @@ fn main() -> Result<()> {
             let res = TerminalApp::initialize(
                 &mut terminal,
                 walk_options,
+                AppOptions {
+                    sorting: opt.sort.map(Into::into).unwrap_or_default(),
+                    byte_vis: opt.byte_vis.into(),
+                    bindings,
+                    theme,
+                    confirm_deletion: !opt.no_confirm,
+                    dry_run: opt.dry_run,
+                },
                 paths_from(input, !opt.stay_on_filesystem)?,
                 Interaction::Full,
             )?
             .map(|(keys_rx, mut app)| {
-                let res = app.process_events(&mut terminal, keys_rx.into_iter());
+                let res = app.run(&mut terminal, keys_rx);
+                // Don't leave entries half-deleted without telling which ones remain.
+                app.state.cancel_deletion();
+                app.wait_for_deletion();
 
                 let res = res.map(|r| {
                     (
@@ -99,8 +141,9 @@ fn main() -> Result<()> {
                 stdout_locked,
                 stderr_if_tty(),
                 walk_options,
+                output_style,
                 !no_total,
-                !no_sort,
+                (!no_sort).then_some(aggregate_sorting),
                 paths_from(input, !opt.stay_on_filesystem)?,
             )?;
             if statistics {
@@ -108,6 +151,37 @@ fn main() -> Result<()> {
             }
             res
         }
+        Some(Report {
+            html,
+            min_size,
+            top_files,
+            input,
+        }) => {
+            use anyhow::Context;
+
+            let options = dua::ReportOptions {
+                size_kind: opt.apparent_size.into(),
+                byte_format: walk_options.byte_format,
+                min_size,
+                top_files,
+            };
+            let traversal = dua::traverse::Traversal::from_walk(
+                walk_options,
+                paths_from(input, !opt.stay_on_filesystem)?,
+                |_| Ok(false),
+            )?
+            .expect("traversals are only aborted on request");
+            let mut out = io::BufWriter::new(
+                fs::File::create(&html)
+                    .with_context(|| format!("Could not create report at '{}'", html.display()))?,
+            );
+            dua::html_report(&mut out, &traversal, options)
+                .and_then(|_| out.flush())
+                .with_context(|| format!("Could not write report to '{}'", html.display()))?;
+            dua::WalkResult {
+                num_errors: traversal.io_errors,
+            }
+        }
         None => {
             let stdout = io::stdout();
             let stdout_locked = stdout.lock();
@@ -115,8 +189,9 @@ fn main() -> Result<()> {
                 stdout_locked,
                 stderr_if_tty(),
                 walk_options,
+                output_style,
                 true,
-                true,
+                Some(aggregate_sorting),
                 paths_from(opt.input, !opt.stay_on_filesystem)?,
             )?
             .0
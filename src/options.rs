@@ -1,7 +1,8 @@
 use dua::ByteFormat as LibraryByteFormat;
 use std::path::PathBuf;
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, clap::ValueEnum)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ByteFormat {
     Metric,
     Binary,
@@ -26,6 +27,105 @@ impl From<ByteFormat> for LibraryByteFormat {
     }
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortMode {
+    SizeDescending,
+    SizeAscending,
+    NameAscending,
+    NameDescending,
+    CountDescending,
+    CountAscending,
+    MtimeDescending,
+    MtimeAscending,
+    ExtensionAscending,
+    ExtensionDescending,
+}
+
+impl From<SortMode> for dua::SortMode {
+    fn from(input: SortMode) -> Self {
+        use dua::SortMode as Sort;
+        match input {
+            SortMode::SizeDescending => Sort::SizeDescending,
+            SortMode::SizeAscending => Sort::SizeAscending,
+            SortMode::NameAscending => Sort::NameAscending,
+            SortMode::NameDescending => Sort::NameDescending,
+            SortMode::CountDescending => Sort::CountDescending,
+            SortMode::CountAscending => Sort::CountAscending,
+            SortMode::MtimeDescending => Sort::MTimeDescending,
+            SortMode::MtimeAscending => Sort::MTimeAscending,
+            SortMode::ExtensionAscending => Sort::ExtensionAscending,
+            SortMode::ExtensionDescending => Sort::ExtensionDescending,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ByteVisualization {
+    Percentage,
+    Bar,
+    LongBar,
+    PercentageAndBar,
+}
+
+#[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+impl From<ByteVisualization> for crate::interactive::ByteVisualization {
+    fn from(input: ByteVisualization) -> Self {
+        use crate::interactive::ByteVisualization as Vis;
+        match input {
+            ByteVisualization::Percentage => Vis::Percentage,
+            ByteVisualization::Bar => Vis::Bar,
+            ByteVisualization::LongBar => Vis::LongBar,
+            ByteVisualization::PercentageAndBar => Vis::PercentageAndBar,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    Dark,
+    Light,
+    HighContrast,
+    Monochrome,
+}
+
+#[cfg(any(feature = "tui-unix", feature = "tui-crossplatform"))]
+impl From<Theme> for crate::interactive::widgets::ThemePreset {
+    fn from(input: Theme) -> Self {
+        use crate::interactive::widgets::ThemePreset;
+        match input {
+            Theme::Dark => ThemePreset::Dark,
+            Theme::Light => ThemePreset::Light,
+            Theme::HighContrast => ThemePreset::HighContrast,
+            Theme::Monochrome => ThemePreset::Monochrome,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorMode {
+    /// Use colors if the output is a terminal and `NO_COLOR` isn't set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Return `true` if colors should be used for output which goes to a terminal if `is_terminal` is set.
+    pub fn is_enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => {
+                is_terminal && std::env::var_os("NO_COLOR").unwrap_or_default().is_empty()
+            }
+        }
+    }
+}
+
 fn dft_format() -> ByteFormat {
     if cfg!(target_vendor = "apple") {
         ByteFormat::Metric
@@ -35,9 +135,15 @@ fn dft_format() -> ByteFormat {
 }
 
 /// A tool to learn about disk usage, fast!
+///
+/// Defaults for options can be set in `$XDG_CONFIG_HOME/dua/config.toml` and in the `DUA_OPTS`
+/// environment variable, with options given on the command-line taking precedence.
 #[derive(Debug, clap::Parser)]
 #[clap(name = "dua", version)]
 #[clap(override_usage = "dua [FLAGS] [OPTIONS] [SUBCOMMAND] [INPUT]...")]
+// Options in DUA_OPTS precede the ones on the command-line, where giving them again must override them
+// instead of failing. Options taking multiple values, like --ignore-dirs, aren't affected.
+#[clap(args_override_self = true)]
 pub struct Args {
     #[clap(subcommand)]
     pub command: Option<Command>,
@@ -65,6 +171,12 @@ pub struct Args {
     #[clap(short = 'l', long)]
     pub count_hard_links: bool,
 
+    /// Do not count the space occupied by directories themselves, only the one of their contents.
+    ///
+    /// By default directories are counted just like `du` does.
+    #[clap(long)]
+    pub no_directory_sizes: bool,
+
     /// If set, we will not cross filesystems or traverse mount points
     #[clap(short = 'x', long)]
     pub stay_on_filesystem: bool,
@@ -76,6 +188,43 @@ pub struct Args {
     #[cfg_attr(target_os = "linux", clap(default_values = &["/proc", "/dev", "/sys", "/run"]))]
     pub ignore_dirs: Vec<PathBuf>,
 
+    /// The order of entries, by size, name, amount of contained entries, modification time or extension.
+    ///
+    /// Defaults to size-descending in interactive mode, and to size-ascending for aggregates so the largest is printed last.
+    #[clap(long, value_enum)]
+    pub sort: Option<SortMode>,
+
+    /// The way to visualize the share of each entry in interactive mode.
+    #[clap(long, value_enum, default_value_t = ByteVisualization::PercentageAndBar)]
+    pub byte_vis: ByteVisualization,
+
+    /// The colors to use in interactive mode. Individual colors can be changed in the configuration file.
+    #[clap(long, value_enum, default_value_t = Theme::Dark)]
+    pub theme: Theme,
+
+    /// Color the names of entries in interactive mode according to the `LS_COLORS` environment variable.
+    #[clap(long)]
+    pub ls_colors: bool,
+
+    /// When to use colors, in interactive mode as well as for aggregates.
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+
+    /// Print paths of aggregates as hyperlinks, which can be clicked in supporting terminals.
+    ///
+    /// Only takes effect if colors are used as well.
+    #[clap(long)]
+    pub hyperlinks: bool,
+
+    /// Delete or trash marked entries in interactive mode without asking for confirmation first.
+    #[clap(long)]
+    pub no_confirm: bool,
+
+    /// Instead of deleting or trashing marked entries in interactive mode, write a shell script doing so to this file for review,
+    /// unless it exists already.
+    #[clap(long, value_name = "FILE")]
+    pub dry_run: Option<PathBuf>,
+
     /// One or more input files or directories. If unset, we will use all entries in the current working directory.
     #[clap(value_parser)]
     pub input: Vec<PathBuf>,
@@ -98,7 +247,7 @@ pub enum Command {
         #[clap(long = "stats")]
         statistics: bool,
         /// If set, paths will be printed in their order of occurrence on the command-line.
-        /// Otherwise they are sorted by their size in bytes, ascending.
+        /// Otherwise they are sorted according to `--sort`.
         #[clap(long)]
         no_sort: bool,
         /// If set, no total column will be computed for multiple inputs
@@ -108,4 +257,26 @@ pub enum Command {
         #[clap(value_parser)]
         input: Vec<PathBuf>,
     },
+    /// Write a report of the consumed space which can be viewed in a web browser
+    #[clap(name = "report")]
+    Report {
+        /// The file to write a self-contained HTML page to, with a collapsible tree, a treemap and the largest files
+        #[clap(long, value_name = "FILE")]
+        html: PathBuf,
+        /// Entries smaller than this are summarized per directory instead of being listed, like "100KB" or "1MiB"
+        #[clap(long, value_name = "SIZE", default_value = "1MB", value_parser = parse_size)]
+        min_size: u128,
+        /// The amount of largest files to list
+        #[clap(long, value_name = "COUNT", default_value_t = 100)]
+        top_files: usize,
+        /// One or more input files or directories. If unset, we will use all entries in the current working directory.
+        #[clap(value_parser)]
+        input: Vec<PathBuf>,
+    },
+}
+
+fn parse_size(size: &str) -> Result<u128, String> {
+    byte_unit::Byte::from_str(size)
+        .map(|b| b.get_bytes())
+        .map_err(|err| err.to_string())
 }
@@ -0,0 +1,251 @@
+use crate::{
+    traverse::{SizeKind, Traversal, Tree, TreeIndex},
+    ByteFormat,
+};
+use itertools::Itertools;
+use petgraph::Direction;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The page the report data is inserted into, in place of its `{{DATA}}` placeholder.
+const TEMPLATE: &str = include_str!("report.html");
+
+/// Configure what goes into a report written by [`html_report()`].
+#[derive(Clone, Copy)]
+pub struct ReportOptions {
+    /// The kind of size to report for each entry.
+    pub size_kind: SizeKind,
+    /// How to display sizes.
+    pub byte_format: ByteFormat,
+    /// Entries smaller than this many bytes are summarized per directory instead of being listed.
+    pub min_size: u128,
+    /// The amount of largest files to list in a table.
+    pub top_files: usize,
+}
+
+/// Write a self-contained HTML page to `out` which shows the entries of `traversal` as a collapsible tree,
+/// as a treemap and as a table of the largest files, and which works without network access.
+pub fn html_report(
+    mut out: impl io::Write,
+    traversal: &Traversal,
+    options: ReportOptions,
+) -> io::Result<()> {
+    let (head, tail) = TEMPLATE
+        .split_once("{{DATA}}")
+        .expect("the template has a placeholder for the data");
+    out.write_all(head.as_bytes())?;
+    out.write_all(report_data(traversal, options).as_bytes())?;
+    out.write_all(tail.as_bytes())
+}
+
+/// Serialize the pruned tree of `traversal` along with its largest files into a JSON object.
+fn report_data(traversal: &Traversal, options: ReportOptions) -> String {
+    let tree = &traversal.tree;
+    // A single input is the root of the report, multiple ones are shown underneath their total.
+    let (root, root_name) = match children(tree, traversal.root_index).collect::<Vec<_>>()[..] {
+        [single] => (single, tree[single].name.display().to_string()),
+        _ => (traversal.root_index, "total".to_owned()),
+    };
+
+    let mut report = Report {
+        tree,
+        options,
+        json: String::new(),
+        top_files: BinaryHeap::new(),
+    };
+    report.json.push_str("{\"title\":");
+    push_json_str(&mut report.json, &format!("Disk usage of {}", root_name));
+    let kind = match options.size_kind {
+        SizeKind::OnDisk => "on disk",
+        SizeKind::Apparent => "of apparent size",
+    };
+    write!(
+        report.json,
+        ",\"kind\":\"{}\",\"entries\":{},\"min_size\":",
+        kind, traversal.entries_traversed
+    )
+    .ok();
+    report.push_size(options.min_size);
+    report.json.push_str(",\"root\":");
+    report.push_node(root, &root_name, &tree[root].name);
+
+    report.json.push_str(",\"top\":[");
+    let top_files = std::mem::take(&mut report.top_files).into_sorted_vec();
+    for (pos, Reverse((size, path))) in top_files.into_iter().enumerate() {
+        if pos > 0 {
+            report.json.push(',');
+        }
+        report.json.push('[');
+        push_json_str(&mut report.json, &path.display().to_string());
+        report.json.push(',');
+        report.push_size(size);
+        report.json.push(']');
+    }
+    report.json.push_str("]}");
+    report.json
+}
+
+struct Report<'a> {
+    tree: &'a Tree,
+    options: ReportOptions,
+    json: String,
+    /// The largest files seen so far, with the smallest of them on top to be replaced first.
+    top_files: BinaryHeap<Reverse<(u128, PathBuf)>>,
+}
+
+impl Report<'_> {
+    /// Write `bytes` in a human-readable format, as string.
+    fn push_size(&mut self, bytes: u128) {
+        let human = self.options.byte_format.display(bytes).to_string();
+        push_json_str(&mut self.json, &human.split_whitespace().join(" "));
+    }
+
+    /// Write the entry at `node_idx`, whose full path is `path`, and all of its children that are large enough.
+    fn push_node(&mut self, node_idx: TreeIndex, name: &str, path: &Path) {
+        let size = self.tree[node_idx].size(self.options.size_kind);
+        self.json.push_str("{\"n\":");
+        push_json_str(&mut self.json, name);
+        write!(self.json, ",\"s\":{},\"h\":", size).ok();
+        self.push_size(size);
+
+        let children: Vec<_> = children(self.tree, node_idx)
+            .map(|idx| (idx, self.tree[idx].size(self.options.size_kind)))
+            .sorted_by(|(_, l), (_, r)| r.cmp(l))
+            .collect();
+        if children.is_empty() {
+            self.json.push('}');
+            // Leaves of the tree are files or empty directories, of which only files are listed.
+            if !self.tree[node_idx].is_dir {
+                self.consider_top_file(size, path);
+            }
+            return;
+        }
+
+        self.json.push_str(",\"c\":[");
+        // Children are sorted by size, so the ones too small to be listed are at the end.
+        let listed = children
+            .iter()
+            .take_while(|(_, size)| *size >= self.options.min_size)
+            .count();
+        for (pos, (child_idx, _)) in children[..listed].iter().enumerate() {
+            if pos > 0 {
+                self.json.push(',');
+            }
+            let name = &self.tree[*child_idx].name;
+            self.push_node(*child_idx, &name.to_string_lossy(), &path.join(name));
+        }
+        self.json.push(']');
+        let pruned = &children[listed..];
+        if !pruned.is_empty() {
+            let pruned_bytes = pruned.iter().map(|(_, size)| size).sum();
+            write!(self.json, ",\"o\":[{},", pruned.len()).ok();
+            self.push_size(pruned_bytes);
+            self.json.push(']');
+        }
+        self.json.push('}');
+    }
+
+    fn consider_top_file(&mut self, size: u128, path: &Path) {
+        let wanted = self.options.top_files;
+        if wanted == 0
+            || (self.top_files.len() == wanted
+                && self
+                    .top_files
+                    .peek()
+                    .is_some_and(|Reverse((smallest, _))| size <= *smallest))
+        {
+            return;
+        }
+        self.top_files.push(Reverse((size, path.to_owned())));
+        if self.top_files.len() > wanted {
+            self.top_files.pop();
+        }
+    }
+}
+
+fn children(tree: &Tree, node_idx: TreeIndex) -> impl Iterator<Item = TreeIndex> + '_ {
+    tree.neighbors_directed(node_idx, Direction::Outgoing)
+}
+
+/// Append `s` as JSON string to `out`, escaping `<` as well so the result can be embedded in a `<script>` tag.
+fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '<' => out.push_str("\\u003c"),
+            c if c.is_control() => {
+                write!(out, "\\u{:04x}", c as u32).ok();
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse::EntryData;
+
+    fn entry(name: &str, size: u128) -> EntryData {
+        EntryData {
+            name: name.into(),
+            size_on_disk: size,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn small_entries_are_summarized_and_names_are_escaped() {
+        let mut tree = Tree::new();
+        let root_index = tree.add_node(EntryData::default());
+        let top = tree.add_node(entry("top</script>", 550));
+        tree.add_edge(root_index, top, ());
+        let empty_dir = tree.add_node(EntryData {
+            is_dir: true,
+            ..entry("empty", 250)
+        });
+        tree.add_edge(top, empty_dir, ());
+        for (name, size) in [("big \"one\"", 200), ("small", 60), ("tiny", 40)] {
+            let idx = tree.add_node(entry(name, size));
+            tree.add_edge(top, idx, ());
+        }
+        let traversal = Traversal {
+            tree,
+            root_index,
+            entries_traversed: 5,
+            start: std::time::Instant::now(),
+            elapsed: None,
+            io_errors: 0,
+            hard_links: Default::default(),
+        };
+
+        let data = report_data(
+            &traversal,
+            ReportOptions {
+                size_kind: SizeKind::OnDisk,
+                byte_format: ByteFormat::Bytes,
+                min_size: 100,
+                top_files: 2,
+            },
+        );
+        assert_eq!(
+            data,
+            concat!(
+                r#"{"title":"Disk usage of top\u003c/script>","kind":"on disk","entries":5,"min_size":"100 b","#,
+                r#""root":{"n":"top\u003c/script>","s":550,"h":"550 b","c":["#,
+                r#"{"n":"empty","s":250,"h":"250 b"},"#,
+                r#"{"n":"big \"one\"","s":200,"h":"200 b"}],"o":[2,"100 b"]},"#,
+                r#""top":[["top\u003c/script>/big \"one\"","200 b"]]}"#
+            )
+        );
+    }
+}
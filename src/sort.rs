@@ -0,0 +1,180 @@
+use std::{cmp::Ordering, fmt, iter::Peekable, path::Path, str::Chars, time::SystemTime};
+
+/// The order in which entries are listed, by one of their properties and in a direction.
+#[derive(Default, Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+pub enum SortMode {
+    #[default]
+    SizeDescending,
+    SizeAscending,
+    NameAscending,
+    NameDescending,
+    CountDescending,
+    CountAscending,
+    MTimeDescending,
+    MTimeAscending,
+    ExtensionAscending,
+    ExtensionDescending,
+}
+
+/// The properties of an entry which can be sorted by.
+pub struct Sortable<'a> {
+    pub name: &'a Path,
+    pub size: u128,
+    /// The amount of entries contained in this one, recursively.
+    pub entry_count: u64,
+    pub mtime: Option<SystemTime>,
+}
+
+impl SortMode {
+    /// Sort by size, or reverse the order if sorting by size already.
+    pub fn toggle_size(&mut self) {
+        use SortMode::*;
+        *self = match self {
+            SizeDescending => SizeAscending,
+            SizeAscending => SizeDescending,
+            _ => SizeDescending,
+        }
+    }
+
+    /// Sort by the same property, but in the opposite direction.
+    pub fn reverse(&mut self) {
+        use SortMode::*;
+        *self = match self {
+            SizeDescending => SizeAscending,
+            SizeAscending => SizeDescending,
+            NameAscending => NameDescending,
+            NameDescending => NameAscending,
+            CountDescending => CountAscending,
+            CountAscending => CountDescending,
+            MTimeDescending => MTimeAscending,
+            MTimeAscending => MTimeDescending,
+            ExtensionAscending => ExtensionDescending,
+            ExtensionDescending => ExtensionAscending,
+        }
+    }
+
+    /// Sort by the next property, in the direction most useful for it.
+    pub fn cycle(&mut self) {
+        use SortMode::*;
+        *self = match self {
+            SizeDescending | SizeAscending => NameAscending,
+            NameAscending | NameDescending => CountDescending,
+            CountDescending | CountAscending => MTimeDescending,
+            MTimeDescending | MTimeAscending => ExtensionAscending,
+            ExtensionAscending | ExtensionDescending => SizeDescending,
+        }
+    }
+
+    /// Compare `l` and `r` in the order of this mode.
+    ///
+    /// Names are compared in natural order, and entries with the same extension are ordered by name.
+    pub fn compare(self, l: &Sortable<'_>, r: &Sortable<'_>) -> Ordering {
+        use SortMode::*;
+        let by_extension = |l: &Sortable<'_>, r: &Sortable<'_>| {
+            l.name
+                .extension()
+                .map(|ext| ext.to_string_lossy())
+                .cmp(&r.name.extension().map(|ext| ext.to_string_lossy()))
+                .then_with(|| natural_cmp(&l.name.to_string_lossy(), &r.name.to_string_lossy()))
+        };
+        match self {
+            SizeDescending => r.size.cmp(&l.size),
+            SizeAscending => l.size.cmp(&r.size),
+            NameAscending => natural_cmp(&l.name.to_string_lossy(), &r.name.to_string_lossy()),
+            NameDescending => natural_cmp(&r.name.to_string_lossy(), &l.name.to_string_lossy()),
+            CountDescending => r.entry_count.cmp(&l.entry_count),
+            CountAscending => l.entry_count.cmp(&r.entry_count),
+            MTimeDescending => r.mtime.cmp(&l.mtime),
+            MTimeAscending => l.mtime.cmp(&r.mtime),
+            ExtensionAscending => by_extension(l, r),
+            ExtensionDescending => by_extension(r, l),
+        }
+    }
+}
+
+impl fmt::Display for SortMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SortMode::*;
+        f.write_str(match self {
+            SizeDescending => "size ↓",
+            SizeAscending => "size ↑",
+            NameAscending => "name ↑",
+            NameDescending => "name ↓",
+            CountDescending => "count ↓",
+            CountAscending => "count ↑",
+            MTimeDescending => "mtime ↓",
+            MTimeAscending => "mtime ↑",
+            ExtensionAscending => "extension ↑",
+            ExtensionDescending => "extension ↓",
+        })
+    }
+}
+
+/// Compare `l` and `r` the way humans would, ignoring case and comparing numbers by value,
+/// so that `file2` comes before `file10`.
+pub fn natural_cmp(l: &str, r: &str) -> Ordering {
+    fn take_number(chars: &mut Peekable<Chars<'_>>) -> String {
+        let mut number = String::new();
+        while let Some(c) = chars.next_if(char::is_ascii_digit) {
+            number.push(c);
+        }
+        number
+    }
+
+    let (mut lc, mut rc) = (l.chars().peekable(), r.chars().peekable());
+    loop {
+        let ordering = match (lc.peek().copied(), rc.peek().copied()) {
+            (None, None) => return l.cmp(r),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(lch), Some(rch)) if lch.is_ascii_digit() && rch.is_ascii_digit() => {
+                let (ln, rn) = (take_number(&mut lc), take_number(&mut rc));
+                let (ln, rn) = (ln.trim_start_matches('0'), rn.trim_start_matches('0'));
+                ln.len().cmp(&rn.len()).then_with(|| ln.cmp(rn))
+            }
+            (Some(lch), Some(rch)) => {
+                lc.next();
+                rc.next();
+                lch.to_lowercase().cmp(rch.to_lowercase())
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_compared_in_natural_order() {
+        let mut names = vec!["file10", "File2", "file1", "a", "file02b", "file2a", "B"];
+        names.sort_by(|l, r| natural_cmp(l, r));
+        assert_eq!(
+            names,
+            vec!["a", "B", "file1", "File2", "file2a", "file02b", "file10"]
+        );
+    }
+
+    #[test]
+    fn reversing_keeps_the_property_and_cycling_visits_all() {
+        let mut mode = SortMode::default();
+        let mut seen = Vec::new();
+        loop {
+            let mut reversed = mode;
+            reversed.reverse();
+            assert_ne!(reversed, mode);
+            reversed.reverse();
+            assert_eq!(reversed, mode);
+
+            seen.push(mode);
+            mode.cycle();
+            if mode == SortMode::default() {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 5, "every property is visited once");
+    }
+}
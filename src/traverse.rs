@@ -1,24 +1,169 @@
-use crate::{crossdev, get_size_or_panic, InodeFilter, Throttle, WalkOptions};
+use crate::{
+    crossdev, dev_inode_nlinks, get_entry_or_panic, InodeFilter, Sortable, Throttle, WalkOptions,
+};
 use anyhow::Result;
 use filesize::PathExt;
 use petgraph::{graph::NodeIndex, stable_graph::StableGraph, Directed, Direction};
 use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
     fs::Metadata,
     io,
+    ops::{AddAssign, Sub},
     path::{Path, PathBuf},
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 pub type TreeIndex = NodeIndex;
 pub type Tree = StableGraph<EntryData, (), Directed>;
 
+/// Identify which of the sizes recorded for each entry to use.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SizeKind {
+    /// The amount of bytes actually allocated on disk.
+    #[default]
+    OnDisk,
+    /// The amount of bytes a file claims to have, i.e. its length.
+    Apparent,
+}
+
+impl SizeKind {
+    pub fn toggle(&mut self) {
+        use SizeKind::*;
+        *self = match self {
+            OnDisk => Apparent,
+            Apparent => OnDisk,
+        }
+    }
+}
+
+impl From<bool> for SizeKind {
+    fn from(apparent_size: bool) -> Self {
+        if apparent_size {
+            SizeKind::Apparent
+        } else {
+            SizeKind::OnDisk
+        }
+    }
+}
+
 #[derive(Eq, PartialEq, Debug, Default, Clone)]
 pub struct EntryData {
     pub name: PathBuf,
-    /// The entry's size in bytes. If it's a directory, the size is the aggregated file size of all children
-    pub size: u128,
+    /// The entry's size on disk in bytes. If it's a directory, the size is the aggregated disk usage of all children
+    pub size_on_disk: u128,
+    /// The entry's apparent size in bytes. If it's a directory, the size is the aggregated file size of all children
+    pub apparent_size: u128,
+    /// The amount of entries in this one if it's a directory, recursively.
+    pub entry_count: u64,
+    /// The time the entry was last modified at, if it could be obtained.
+    pub mtime: Option<SystemTime>,
     /// If set, the item meta-data could not be obtained
     pub metadata_io_error: bool,
+    /// If set, the entry is a directory, which is never the case for symlinks to directories.
+    pub is_dir: bool,
+}
+
+impl EntryData {
+    /// Return the size in bytes of the given `kind`.
+    pub fn size(&self, kind: SizeKind) -> u128 {
+        match kind {
+            SizeKind::OnDisk => self.size_on_disk,
+            SizeKind::Apparent => self.apparent_size,
+        }
+    }
+
+    /// The properties to sort this entry by, using sizes of the given `kind`.
+    pub fn sortable(&self, kind: SizeKind) -> Sortable<'_> {
+        Sortable {
+            name: &self.name,
+            size: self.size(kind),
+            entry_count: self.entry_count,
+            mtime: self.mtime,
+        }
+    }
+}
+/// The device and inode numbers identifying a file.
+pub type DevInode = (u64, u64);
+
+/// A file with more than one hard link, whose space is only freed once all of its links are deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inode {
+    /// The amount of links to the file, including those outside of the traversal.
+    pub nlinks: u64,
+    pub size_on_disk: u128,
+    pub apparent_size: u128,
+}
+
+impl Inode {
+    /// Return the size in bytes of the given `kind`.
+    pub fn size(&self, kind: SizeKind) -> u128 {
+        match kind {
+            SizeKind::OnDisk => self.size_on_disk,
+            SizeKind::Apparent => self.apparent_size,
+        }
+    }
+}
+
+/// The entries of a traversal that are links to files with more than one hard link.
+///
+/// Files with a single link aren't recorded as deleting them always frees their space.
+#[derive(Debug, Default, Clone)]
+pub struct HardLinks {
+    /// The file each entry links to.
+    pub links: HashMap<TreeIndex, DevInode>,
+    pub inodes: HashMap<DevInode, Inode>,
+}
+
+impl HardLinks {
+    /// Forget the entry at `index` as it was deleted, which leaves one link less to the file it linked to.
+    pub fn forget(&mut self, index: TreeIndex) {
+        if let Some(dev_inode) = self.links.remove(&index) {
+            if let Entry::Occupied(mut inode) = self.inodes.entry(dev_inode) {
+                inode.get_mut().nlinks -= 1;
+                if inode.get().nlinks == 0 {
+                    inode.remove();
+                }
+            }
+        }
+    }
+}
+
+/// Sizes accumulated while walking a level of the tree.
+#[derive(Default, Debug, Copy, Clone)]
+struct Sizes {
+    on_disk: u128,
+    apparent: u128,
+    /// The amount of entries, each of which counts itself.
+    entries: u64,
+}
+
+impl Sizes {
+    /// The sizes of a single entry which takes no space.
+    const ONE_ENTRY: Sizes = Sizes {
+        on_disk: 0,
+        apparent: 0,
+        entries: 1,
+    };
+}
+
+impl AddAssign for Sizes {
+    fn add_assign(&mut self, rhs: Self) {
+        self.on_disk += rhs.on_disk;
+        self.apparent += rhs.apparent;
+        self.entries += rhs.entries;
+    }
+}
+
+impl Sub for Sizes {
+    type Output = Sizes;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Sizes {
+            on_disk: self.on_disk - rhs.on_disk,
+            apparent: self.apparent - rhs.apparent,
+            entries: self.entries - rhs.entries,
+        }
+    }
 }
 
 /// The result of the previous filesystem traversal
@@ -36,8 +181,8 @@ pub struct Traversal {
     pub elapsed: Option<std::time::Duration>,
     /// Total amount of IO errors encountered when traversing the filesystem
     pub io_errors: u64,
-    /// Total amount of bytes seen during the traversal
-    pub total_bytes: Option<u128>,
+    /// The files with more than one hard link.
+    pub hard_links: HardLinks,
 }
 
 impl Traversal {
@@ -46,17 +191,20 @@ impl Traversal {
         input: Vec<PathBuf>,
         mut update: impl FnMut(&mut Traversal) -> Result<bool>,
     ) -> Result<Option<Traversal>> {
-        fn set_size_or_panic(tree: &mut Tree, node_idx: TreeIndex, current_size_at_depth: u128) {
-            tree.node_weight_mut(node_idx)
-                .expect("node for parent index we just retrieved")
-                .size = current_size_at_depth;
+        fn set_size_or_panic(tree: &mut Tree, node_idx: TreeIndex, current_size_at_depth: Sizes) {
+            let entry = tree
+                .node_weight_mut(node_idx)
+                .expect("node for parent index we just retrieved");
+            entry.size_on_disk = current_size_at_depth.on_disk;
+            entry.apparent_size = current_size_at_depth.apparent;
+            entry.entry_count = current_size_at_depth.entries - 1;
         }
         fn parent_or_panic(tree: &mut Tree, parent_node_idx: TreeIndex) -> TreeIndex {
             tree.neighbors_directed(parent_node_idx, Direction::Incoming)
                 .next()
                 .expect("every node in the iteration has a parent")
         }
-        fn pop_or_panic(v: &mut Vec<u128>) -> u128 {
+        fn pop_or_panic(v: &mut Vec<Sizes>) -> Sizes {
             v.pop().expect("sizes per level to be in sync with graph")
         }
 
@@ -70,13 +218,14 @@ impl Traversal {
                 start: std::time::Instant::now(),
                 elapsed: None,
                 io_errors: 0,
-                total_bytes: None,
+                hard_links: HardLinks::default(),
             }
         };
 
         let (mut previous_node_idx, mut parent_node_idx) = (t.root_index, t.root_index);
         let mut sizes_per_depth_level = Vec::new();
-        let mut current_size_at_depth: u128 = 0;
+        let mut current_size_at_depth = Sizes::default();
+        let mut previous_size = Sizes::default();
         let mut previous_depth = 0;
         let mut inodes = InodeFilter::default();
 
@@ -117,38 +266,41 @@ impl Traversal {
                         } else {
                             entry.file_name.into()
                         };
+                        data.is_dir = entry.file_type.is_dir();
+                        if let Some(Ok(m)) = &entry.client_state {
+                            data.mtime = m.modified().ok();
+                        }
                         let file_size = match &entry.client_state {
                             Some(Ok(ref m))
-                                if !m.is_dir()
-                                    && (walk_options.count_hard_links || inodes.add(m))
-                                    && (walk_options.cross_filesystems
-                                        || crossdev::is_same_device(device_id, m)) =>
+                                if walk_options.is_counted(m, &mut inodes, device_id) =>
                             {
-                                if walk_options.apparent_size {
-                                    m.len()
-                                } else {
-                                    size_on_disk(&entry.parent_path, &data.name, m).unwrap_or_else(
-                                        |_| {
+                                Sizes {
+                                    on_disk: size_on_disk(&entry.parent_path, &data.name, m)
+                                        .unwrap_or_else(|_| {
                                             t.io_errors += 1;
                                             data.metadata_io_error = true;
                                             0
-                                        },
-                                    )
+                                        }) as u128,
+                                    apparent: m.len() as u128,
+                                    entries: 1,
                                 }
                             }
-                            Some(Ok(_)) => 0,
+                            Some(Ok(_)) => Sizes::ONE_ENTRY,
                             Some(Err(_)) => {
                                 t.io_errors += 1;
                                 data.metadata_io_error = true;
-                                0
+                                Sizes::ONE_ENTRY
                             }
-                            None => 0, // a directory
-                        } as u128;
+                            None => Sizes::ONE_ENTRY, // neither file, symlink nor directory
+                        };
 
                         match (entry.depth, previous_depth) {
                             (n, p) if n > p => {
-                                sizes_per_depth_level.push(current_size_at_depth);
-                                current_size_at_depth = file_size;
+                                // The parent's own size moves from its level into the one of its children,
+                                // which is where directory sizes are taken from.
+                                sizes_per_depth_level.push(current_size_at_depth - previous_size);
+                                current_size_at_depth = previous_size;
+                                current_size_at_depth += file_size;
                                 parent_node_idx = previous_node_idx;
                             }
                             (n, p) if n < p => {
@@ -174,11 +326,36 @@ impl Traversal {
                             }
                         };
 
-                        data.size = file_size;
+                        let parent_path = &entry.parent_path;
+                        let hard_link = match &entry.client_state {
+                            Some(Ok(m)) if !m.is_dir() => dev_inode_nlinks(m)
+                                .filter(|(_, nlinks)| *nlinks > 1)
+                                .map(|(dev_inode, nlinks)| {
+                                    let inode = Inode {
+                                        nlinks,
+                                        size_on_disk: size_on_disk(parent_path, &data.name, m)
+                                            .unwrap_or_default()
+                                            as u128,
+                                        apparent_size: m.len() as u128,
+                                    };
+                                    (dev_inode, inode)
+                                }),
+                            _ => None,
+                        };
+                        if let Some((dev_inode, inode)) = hard_link {
+                            t.hard_links.inodes.entry(dev_inode).or_insert(inode);
+                        }
+
+                        data.size_on_disk = file_size.on_disk;
+                        data.apparent_size = file_size.apparent;
                         let entry_index = t.tree.add_node(data);
+                        if let Some((dev_inode, _)) = hard_link {
+                            t.hard_links.links.insert(entry_index, dev_inode);
+                        }
 
                         t.tree.add_edge(parent_node_idx, entry_index, ());
                         previous_node_idx = entry_index;
+                        previous_size = file_size;
                         previous_depth = entry.depth;
                     }
                     Err(_) => {
@@ -199,7 +376,7 @@ impl Traversal {
         }
 
         sizes_per_depth_level.push(current_size_at_depth);
-        current_size_at_depth = 0;
+        current_size_at_depth = Sizes::default();
         for _ in 0..previous_depth {
             current_size_at_depth += pop_or_panic(&mut sizes_per_depth_level);
             set_size_or_panic(&mut t.tree, parent_node_idx, current_size_at_depth);
@@ -207,16 +384,67 @@ impl Traversal {
         }
         let root_size = t.recompute_root_size();
         set_size_or_panic(&mut t.tree, t.root_index, root_size);
-        t.total_bytes = Some(root_size);
 
         t.elapsed = Some(t.start.elapsed());
         Ok(Some(t))
     }
 
-    fn recompute_root_size(&self) -> u128 {
-        self.tree
+    /// Total amount of bytes of the given `kind` seen during the traversal, available once it is done.
+    pub fn total_bytes(&self, kind: SizeKind) -> Option<u128> {
+        self.elapsed
+            .map(|_| get_entry_or_panic(&self.tree, self.root_index).size(kind))
+    }
+
+    /// Return the amount of bytes of the given `kind` that deleting `entries` and everything in them frees,
+    /// assuming none of them contains another.
+    ///
+    /// Unlike the sum of their sizes, files with more than one hard link only count if all of their links are
+    /// deleted, and then only once.
+    pub fn freed_bytes(&self, entries: &HashSet<TreeIndex>, kind: SizeKind) -> u128 {
+        let mut freed: u128 = entries
+            .iter()
+            .filter_map(|index| self.tree.node_weight(*index))
+            .map(|entry| entry.size(kind))
+            .sum();
+        let mut deleted_links = HashMap::<DevInode, u64>::new();
+        for (index, dev_inode) in &self.hard_links.links {
+            let mut ancestor = Some(*index);
+            while let Some(index) = ancestor.filter(|index| !entries.contains(index)) {
+                ancestor = self
+                    .tree
+                    .neighbors_directed(index, Direction::Incoming)
+                    .next();
+            }
+            if ancestor.is_some() {
+                freed = freed.saturating_sub(get_entry_or_panic(&self.tree, *index).size(kind));
+                *deleted_links.entry(*dev_inode).or_default() += 1;
+            }
+        }
+        for (dev_inode, num_deleted) in deleted_links {
+            match self.hard_links.inodes.get(&dev_inode) {
+                Some(inode) if num_deleted >= inode.nlinks => freed += inode.size(kind),
+                _ => {}
+            }
+        }
+        freed
+    }
+
+    fn recompute_root_size(&self) -> Sizes {
+        let mut size = Sizes {
+            entries: 1,
+            ..Default::default()
+        };
+        for idx in self
+            .tree
             .neighbors_directed(self.root_index, Direction::Outgoing)
-            .map(|idx| get_size_or_panic(&self.tree, idx))
-            .sum()
+        {
+            let entry = get_entry_or_panic(&self.tree, idx);
+            size += Sizes {
+                on_disk: entry.size_on_disk,
+                apparent: entry.apparent_size,
+                entries: entry.entry_count + 1,
+            };
+        }
+        size
     }
 }